@@ -1,12 +1,48 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20241128_214535_create_media_table;
+mod m20261018_090000_alter_media_created_at;
+mod m20261018_100000_add_media_file_columns;
+mod m20261018_110000_add_media_missing;
+mod m20261018_120000_add_media_episode_columns;
+mod m20261018_130000_add_media_release_columns;
+mod m20261018_140000_create_tv_tables;
+mod m20261018_150000_create_library_table;
+mod m20261018_160000_add_media_watched;
+mod m20261018_170000_add_media_search;
+mod m20261018_180000_create_media_stream_table;
+mod m20261018_190000_add_media_stream_details;
+mod m20261018_200000_add_media_container_columns;
+mod m20261018_210000_add_media_fingerprint;
+mod m20261018_220000_create_item_table;
+mod m20261018_230000_add_media_nfo_columns;
+mod m20261019_000000_create_artwork_table;
+mod m20261019_010000_add_artwork_is_generated;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20241128_214535_create_media_table::Migration)]
+        vec![
+            Box::new(m20241128_214535_create_media_table::Migration),
+            Box::new(m20261018_090000_alter_media_created_at::Migration),
+            Box::new(m20261018_100000_add_media_file_columns::Migration),
+            Box::new(m20261018_110000_add_media_missing::Migration),
+            Box::new(m20261018_120000_add_media_episode_columns::Migration),
+            Box::new(m20261018_130000_add_media_release_columns::Migration),
+            Box::new(m20261018_140000_create_tv_tables::Migration),
+            Box::new(m20261018_150000_create_library_table::Migration),
+            Box::new(m20261018_160000_add_media_watched::Migration),
+            Box::new(m20261018_170000_add_media_search::Migration),
+            Box::new(m20261018_180000_create_media_stream_table::Migration),
+            Box::new(m20261018_190000_add_media_stream_details::Migration),
+            Box::new(m20261018_200000_add_media_container_columns::Migration),
+            Box::new(m20261018_210000_add_media_fingerprint::Migration),
+            Box::new(m20261018_220000_create_item_table::Migration),
+            Box::new(m20261018_230000_add_media_nfo_columns::Migration),
+            Box::new(m20261019_000000_create_artwork_table::Migration),
+            Box::new(m20261019_010000_add_artwork_is_generated::Migration),
+        ]
     }
 }
@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The first migration declared `created_at` as a string column, which sea-orm
+// cannot map onto a `DateTime`. Postgres needs an explicit cast to convert it.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE media \
+                 ALTER COLUMN created_at DROP DEFAULT, \
+                 ALTER COLUMN created_at TYPE timestamp USING created_at::timestamp, \
+                 ALTER COLUMN created_at SET DEFAULT CURRENT_TIMESTAMP, \
+                 ALTER COLUMN created_at SET NOT NULL",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE media \
+                 ALTER COLUMN created_at DROP DEFAULT, \
+                 ALTER COLUMN created_at DROP NOT NULL, \
+                 ALTER COLUMN created_at TYPE varchar USING created_at::varchar, \
+                 ALTER COLUMN created_at SET DEFAULT CURRENT_TIMESTAMP",
+            )
+            .await?;
+
+        Ok(())
+    }
+}
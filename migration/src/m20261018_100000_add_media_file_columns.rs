@@ -0,0 +1,78 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One column per statement, SQLite cannot alter several at once.
+        let columns = [
+            big_integer(Media::Size).default(0).to_owned(),
+            timestamp_null(Media::ModifiedAt),
+            string_null(Media::Container),
+            double_null(Media::Duration),
+        ];
+
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-media-path")
+                    .table(Media::Table)
+                    .col(Media::Path)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-media-path")
+                    .table(Media::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            Media::Size,
+            Media::ModifiedAt,
+            Media::Container,
+            Media::Duration,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    Path,
+    Size,
+    ModifiedAt,
+    Container,
+    Duration,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            string_null(Media::SeriesName),
+            integer_null(Media::SeasonNumber),
+            integer_null(Media::EpisodeNumber),
+            integer_null(Media::EpisodeNumberEnd),
+        ];
+
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-media-series-episode")
+                    .table(Media::Table)
+                    .col(Media::SeriesName)
+                    .col(Media::SeasonNumber)
+                    .col(Media::EpisodeNumber)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-media-series-episode")
+                    .table(Media::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            Media::SeriesName,
+            Media::SeasonNumber,
+            Media::EpisodeNumber,
+            Media::EpisodeNumberEnd,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    SeriesName,
+    SeasonNumber,
+    EpisodeNumber,
+    EpisodeNumberEnd,
+}
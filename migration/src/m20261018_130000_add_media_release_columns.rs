@@ -0,0 +1,67 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            integer_null(Media::Year),
+            string_null(Media::Source),
+            string_null(Media::Resolution),
+            string_null(Media::VideoCodec),
+            string_null(Media::AudioCodec),
+            string_null(Media::Languages),
+            string_null(Media::ReleaseGroup),
+        ];
+
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Media::Year,
+            Media::Source,
+            Media::Resolution,
+            Media::VideoCodec,
+            Media::AudioCodec,
+            Media::Languages,
+            Media::ReleaseGroup,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    Year,
+    Source,
+    Resolution,
+    VideoCodec,
+    AudioCodec,
+    Languages,
+    ReleaseGroup,
+}
@@ -0,0 +1,135 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Series::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Series::Id))
+                    .col(string_uniq(Series::Name))
+                    .col(timestamp(Series::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Season::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Season::Id))
+                    .col(integer(Season::SeriesId))
+                    .col(integer(Season::SeasonNumber))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-season-series_id")
+                            .from(Season::Table, Season::SeriesId)
+                            .to(Series::Table, Series::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-season-series_id-season_number")
+                    .table(Season::Table)
+                    .col(Season::SeriesId)
+                    .col(Season::SeasonNumber)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Episode::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Episode::Id))
+                    .col(integer(Episode::SeasonId))
+                    .col(integer_uniq(Episode::MediaId))
+                    .col(integer(Episode::EpisodeNumber))
+                    .col(integer_null(Episode::EpisodeNumberEnd))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-episode-season_id")
+                            .from(Episode::Table, Episode::SeasonId)
+                            .to(Season::Table, Season::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-episode-media_id")
+                            .from(Episode::Table, Episode::MediaId)
+                            .to(Media::Table, Media::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-episode-season_id-episode_number")
+                    .table(Episode::Table)
+                    .col(Episode::SeasonId)
+                    .col(Episode::EpisodeNumber)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Episode::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Season::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Series::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Series {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Season {
+    Table,
+    Id,
+    SeriesId,
+    SeasonNumber,
+}
+
+#[derive(DeriveIden)]
+enum Episode {
+    Table,
+    Id,
+    SeasonId,
+    MediaId,
+    EpisodeNumber,
+    EpisodeNumberEnd,
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    Id,
+}
@@ -0,0 +1,110 @@
+use sea_orm_migration::sea_orm::DbBackend;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Library::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Library::Id))
+                    .col(string_uniq(Library::Name))
+                    .col(string_len(Library::Kind, 16))
+                    .col(json(Library::Roots))
+                    .col(json(Library::ScanOptions))
+                    .col(timestamp(Library::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Media::Table)
+                    .add_column(integer_null(Media::LibraryId))
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite cannot add a foreign key to an existing table
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .create_foreign_key(
+                    ForeignKey::create()
+                        .name("fk-media-library_id")
+                        .from(Media::Table, Media::LibraryId)
+                        .to(Library::Table, Library::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-media-library_id")
+                    .table(Media::Table)
+                    .col(Media::LibraryId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-media-library_id")
+                    .table(Media::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name("fk-media-library_id")
+                        .table(Media::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Media::Table)
+                    .drop_column(Media::LibraryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Library::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Library {
+    Table,
+    Id,
+    Name,
+    Kind,
+    Roots,
+    ScanOptions,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    LibraryId,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::sea_orm::DbBackend;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [text_null(Media::Overview), text_null(Media::CastMembers)] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Other backends fall back to ranking in the server, see `search_service`
+        if manager.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        let db = manager.get_connection();
+
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+        db.execute_unprepared(
+            "ALTER TABLE media ADD COLUMN search_vector tsvector GENERATED ALWAYS AS ( \
+                 setweight(to_tsvector('simple', coalesce(title, '')), 'A') || \
+                 setweight(to_tsvector('simple', coalesce(series_name, '')), 'A') || \
+                 setweight(to_tsvector('simple', coalesce(cast_members, '')), 'B') || \
+                 setweight(to_tsvector('simple', coalesce(overview, '')), 'C') \
+             ) STORED",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE INDEX idx_media_search_vector ON media USING GIN (search_vector)",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE INDEX idx_media_title_trgm ON media USING GIN (title gin_trgm_ops)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Postgres {
+            let db = manager.get_connection();
+
+            db.execute_unprepared("DROP INDEX IF EXISTS idx_media_title_trgm")
+                .await?;
+            db.execute_unprepared("DROP INDEX IF EXISTS idx_media_search_vector")
+                .await?;
+            db.execute_unprepared("ALTER TABLE media DROP COLUMN IF EXISTS search_vector")
+                .await?;
+        }
+
+        for column in [Media::Overview, Media::CastMembers] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    Overview,
+    CastMembers,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MediaStream::Table)
+                    .if_not_exists()
+                    .col(pk_auto(MediaStream::Id))
+                    .col(integer(MediaStream::MediaId))
+                    .col(integer(MediaStream::StreamIndex))
+                    .col(string(MediaStream::Codec))
+                    .col(string(MediaStream::Medium))
+                    .col(big_integer(MediaStream::Length))
+                    .col(string_null(MediaStream::Language))
+                    .col(integer_null(MediaStream::Width))
+                    .col(integer_null(MediaStream::Height))
+                    .col(integer_null(MediaStream::Channels))
+                    .col(big_integer_null(MediaStream::BitRate))
+                    .col(boolean(MediaStream::IsDefault).default(false))
+                    .col(boolean(MediaStream::IsForced).default(false))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-media_stream-media_id")
+                            .from(MediaStream::Table, MediaStream::MediaId)
+                            .to(Media::Table, Media::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-media_stream-media_id-stream_index")
+                    .table(MediaStream::Table)
+                    .col(MediaStream::MediaId)
+                    .col(MediaStream::StreamIndex)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Forget the stored mtimes so the next scan probes every file once
+        // and fills the new table.
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Media::Table)
+                    .value(Media::ModifiedAt, Option::<String>::None)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MediaStream::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MediaStream {
+    Table,
+    Id,
+    MediaId,
+    StreamIndex,
+    Codec,
+    Medium,
+    Length,
+    Language,
+    Width,
+    Height,
+    Channels,
+    BitRate,
+    IsDefault,
+    IsForced,
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    Id,
+    ModifiedAt,
+}
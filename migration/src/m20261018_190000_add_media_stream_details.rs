@@ -0,0 +1,121 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            double_null(MediaStream::Duration),
+            string_null(MediaStream::Title),
+            string_null(MediaStream::Profile),
+            boolean(MediaStream::IsHearingImpaired)
+                .default(false)
+                .to_owned(),
+            string_null(MediaStream::PixelFormat),
+            double_null(MediaStream::FrameRate),
+            integer_null(MediaStream::Level),
+            string_null(MediaStream::ColorPrimaries),
+            string_null(MediaStream::ColorTransfer),
+            string_null(MediaStream::ColorSpace),
+            string_null(MediaStream::Hdr),
+            integer_null(MediaStream::SampleRate),
+            string_null(MediaStream::ChannelLayout),
+        ];
+
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(MediaStream::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Replaced by the duration in seconds
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MediaStream::Table)
+                    .drop_column(MediaStream::Length)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Probe every file again on the next scan to fill the new columns
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Media::Table)
+                    .value(Media::ModifiedAt, Option::<String>::None)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MediaStream::Table)
+                    .add_column(big_integer(MediaStream::Length).default(0))
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            MediaStream::Duration,
+            MediaStream::Title,
+            MediaStream::Profile,
+            MediaStream::IsHearingImpaired,
+            MediaStream::PixelFormat,
+            MediaStream::FrameRate,
+            MediaStream::Level,
+            MediaStream::ColorPrimaries,
+            MediaStream::ColorTransfer,
+            MediaStream::ColorSpace,
+            MediaStream::Hdr,
+            MediaStream::SampleRate,
+            MediaStream::ChannelLayout,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(MediaStream::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum MediaStream {
+    Table,
+    Length,
+    Duration,
+    Title,
+    Profile,
+    IsHearingImpaired,
+    PixelFormat,
+    FrameRate,
+    Level,
+    ColorPrimaries,
+    ColorTransfer,
+    ColorSpace,
+    Hdr,
+    SampleRate,
+    ChannelLayout,
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    ModifiedAt,
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            big_integer_null(Media::BitRate),
+            double_null(Media::StartTime),
+            json_null(Media::Tags),
+            json_null(Media::Chapters),
+        ];
+
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Probe every file again on the next scan to fill the new columns
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Media::Table)
+                    .value(Media::ModifiedAt, Option::<String>::None)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Media::BitRate,
+            Media::StartTime,
+            Media::Tags,
+            Media::Chapters,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    ModifiedAt,
+    BitRate,
+    StartTime,
+    Tags,
+    Chapters,
+}
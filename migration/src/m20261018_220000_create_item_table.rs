@@ -0,0 +1,116 @@
+use sea_orm_migration::sea_orm::DbBackend;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Item::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Item::Id))
+                    .col(string_uniq(Item::GroupKey))
+                    .col(string_len(Item::Kind, 16))
+                    .col(string(Item::Title))
+                    .col(integer_null(Item::Year))
+                    .col(string_null(Item::SeriesName))
+                    .col(integer_null(Item::SeasonNumber))
+                    .col(integer_null(Item::EpisodeNumber))
+                    .col(timestamp(Item::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Media::Table)
+                    .add_column(integer_null(Media::ItemId))
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite cannot add a foreign key to an existing table
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .create_foreign_key(
+                    ForeignKey::create()
+                        .name("fk-media-item_id")
+                        .from(Media::Table, Media::ItemId)
+                        .to(Item::Table, Item::Id)
+                        .on_delete(ForeignKeyAction::SetNull)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-media-item_id")
+                    .table(Media::Table)
+                    .col(Media::ItemId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-media-item_id")
+                    .table(Media::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name("fk-media-item_id")
+                        .table(Media::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Media::Table)
+                    .drop_column(Media::ItemId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Item::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Item {
+    Table,
+    Id,
+    GroupKey,
+    Kind,
+    Title,
+    Year,
+    SeriesName,
+    SeasonNumber,
+    EpisodeNumber,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    ItemId,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            string_null(Media::OriginalTitle),
+            text_null(Media::Genres),
+            text_null(Media::Studios),
+            json_null(Media::Ratings),
+            integer_null(Media::Runtime),
+            json_null(Media::ExternalIds),
+        ];
+
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Media::OriginalTitle,
+            Media::Genres,
+            Media::Studios,
+            Media::Ratings,
+            Media::Runtime,
+            Media::ExternalIds,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Media::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    OriginalTitle,
+    Genres,
+    Studios,
+    Ratings,
+    Runtime,
+    ExternalIds,
+}
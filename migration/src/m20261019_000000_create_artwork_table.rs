@@ -0,0 +1,74 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Artwork::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Artwork::Id))
+                    .col(integer(Artwork::MediaId))
+                    .col(string_len(Artwork::Kind, 16))
+                    .col(string(Artwork::Path))
+                    .col(integer_null(Artwork::StreamIndex))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-artwork-media_id")
+                            .from(Artwork::Table, Artwork::MediaId)
+                            .to(Media::Table, Media::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-artwork-media_id-kind")
+                    .table(Artwork::Table)
+                    .col(Artwork::MediaId)
+                    .col(Artwork::Kind)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Probe every file again on the next scan to pick up embedded cover art
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Media::Table)
+                    .value(Media::ModifiedAt, Option::<String>::None)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Artwork::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Artwork {
+    Table,
+    Id,
+    MediaId,
+    Kind,
+    Path,
+    StreamIndex,
+}
+
+#[derive(DeriveIden)]
+enum Media {
+    Table,
+    Id,
+    ModifiedAt,
+}
@@ -0,0 +1,53 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ArtworkKind {
+    #[sea_orm(string_value = "poster")]
+    Poster,
+    #[sea_orm(string_value = "fanart")]
+    Fanart,
+    #[sea_orm(string_value = "banner")]
+    Banner,
+    #[sea_orm(string_value = "thumb")]
+    Thumb,
+    #[sea_orm(string_value = "season_poster")]
+    SeasonPoster,
+}
+
+// An image found next to a media file, embedded in it or extracted from it
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "artwork")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub media_id: i32,
+    pub kind: ArtworkKind,
+    // The image file, or the media file itself for embedded images
+    pub path: String,
+    // Attachment stream holding the image when it is embedded
+    pub stream_index: Option<i32>,
+    // Extracted from the video by the server, stored in the cache folder
+    pub is_generated: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::media::Entity",
+        from = "Column::MediaId",
+        to = "super::media::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Media,
+}
+
+impl Related<super::media::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Media.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
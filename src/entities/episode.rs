@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "episode")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub season_id: i32,
+    #[sea_orm(unique)]
+    pub media_id: i32,
+    pub episode_number: i32,
+    pub episode_number_end: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::season::Entity",
+        from = "Column::SeasonId",
+        to = "super::season::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Season,
+    #[sea_orm(
+        belongs_to = "super::media::Entity",
+        from = "Column::MediaId",
+        to = "super::media::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Media,
+}
+
+impl Related<super::season::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Season.def()
+    }
+}
+
+impl Related<super::media::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Media.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
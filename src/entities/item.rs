@@ -0,0 +1,47 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    #[sea_orm(string_value = "movie")]
+    Movie,
+    #[sea_orm(string_value = "episode")]
+    Episode,
+    // Home videos, music and files whose name could not be parsed
+    #[sea_orm(string_value = "other")]
+    Other,
+}
+
+// One logical title, each media file attached to it is a version
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "item")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // `movie:kingsman the secret service:2014`, `episode:foundation:2:3`
+    #[sea_orm(unique)]
+    pub group_key: String,
+    pub kind: ItemKind,
+    pub title: String,
+    pub year: Option<i32>,
+    pub series_name: Option<String>,
+    pub season_number: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::media::Entity")]
+    Media,
+}
+
+impl Related<super::media::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Media.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,73 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryKind {
+    #[sea_orm(string_value = "movies")]
+    Movies,
+    #[sea_orm(string_value = "shows")]
+    Shows,
+    #[sea_orm(string_value = "home_videos")]
+    HomeVideos,
+    #[sea_orm(string_value = "music")]
+    Music,
+    // Movies and shows side by side, the layout of the original `./medias` folder
+    #[sea_orm(string_value = "mixed")]
+    Mixed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct RootPaths(pub Vec<String>);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(default)]
+pub struct ScanOptions {
+    // Follow file system changes under the roots instead of waiting for a scan
+    pub watch: bool,
+    // File extensions to pick up, the kind's defaults when empty
+    pub extensions: Vec<String>,
+    pub include_hidden: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            watch: true,
+            extensions: Vec::new(),
+            include_hidden: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "library")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub kind: LibraryKind,
+    #[sea_orm(column_type = "Json")]
+    pub roots: RootPaths,
+    #[sea_orm(column_type = "Json")]
+    pub scan_options: ScanOptions,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::media::Entity")]
+    Media,
+}
+
+impl Related<super::media::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Media.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
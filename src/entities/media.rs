@@ -0,0 +1,154 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Global container metadata such as `title`, `encoder` or `creation_time`
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct MetadataTags(pub BTreeMap<String, String>);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub id: i64,
+    // Seconds from the start of the file
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct Chapters(pub Vec<Chapter>);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    // Provider of the rating, `imdb`, `tmdb`, ... or `default`
+    pub name: String,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub votes: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct Ratings(pub Vec<Rating>);
+
+// Ids of the title at metadata providers, keyed by provider name
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct ExternalIds(pub BTreeMap<String, String>);
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "media")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub title: String,
+    #[sea_orm(unique)]
+    pub path: String,
+    pub created_at: DateTime,
+    pub size: i64,
+    pub modified_at: Option<DateTime>,
+    pub container: Option<String>,
+    pub duration: Option<f64>,
+    pub missing: bool,
+    pub series_name: Option<String>,
+    pub season_number: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub episode_number_end: Option<i32>,
+    pub year: Option<i32>,
+    pub source: Option<String>,
+    pub resolution: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    // Comma separated language markers such as `MULTi,VOSTFR`
+    pub languages: Option<String>,
+    pub release_group: Option<String>,
+    pub library_id: Option<i32>,
+    pub watched: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub overview: Option<String>,
+    // Comma separated cast names
+    #[sea_orm(column_type = "Text", nullable)]
+    pub cast_members: Option<String>,
+    // Overall bitrate in bits per second
+    pub bit_rate: Option<i64>,
+    pub start_time: Option<f64>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub tags: Option<MetadataTags>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub chapters: Option<Chapters>,
+    // Hash of the size and sampled blocks, equal for copies of the same file
+    pub fingerprint: Option<String>,
+    // The logical title this file is a version of
+    pub item_id: Option<i32>,
+    pub original_title: Option<String>,
+    // Comma separated, like `cast_members`
+    #[sea_orm(column_type = "Text", nullable)]
+    pub genres: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub studios: Option<String>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub ratings: Option<Ratings>,
+    // Minutes, as written in NFO files
+    pub runtime: Option<i32>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub external_ids: Option<ExternalIds>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_one = "super::episode::Entity")]
+    Episode,
+    #[sea_orm(
+        belongs_to = "super::library::Entity",
+        from = "Column::LibraryId",
+        to = "super::library::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Library,
+    #[sea_orm(has_many = "super::media_stream::Entity")]
+    MediaStream,
+    #[sea_orm(
+        belongs_to = "super::item::Entity",
+        from = "Column::ItemId",
+        to = "super::item::Column::Id",
+        on_delete = "SetNull"
+    )]
+    Item,
+    #[sea_orm(has_many = "super::artwork::Entity")]
+    Artwork,
+}
+
+impl Related<super::episode::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Episode.def()
+    }
+}
+
+impl Related<super::library::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Library.def()
+    }
+}
+
+impl Related<super::media_stream::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MediaStream.def()
+    }
+}
+
+impl Related<super::item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Item.def()
+    }
+}
+
+impl Related<super::artwork::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Artwork.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,54 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "media_stream")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub media_id: i32,
+    pub stream_index: i32,
+    pub codec: String,
+    pub medium: String,
+    // Seconds, converted from the stream time base
+    pub duration: Option<f64>,
+    pub bit_rate: Option<i64>,
+    pub profile: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub is_forced: bool,
+    pub is_hearing_impaired: bool,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub pixel_format: Option<String>,
+    pub frame_rate: Option<f64>,
+    pub level: Option<i32>,
+    pub color_primaries: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_space: Option<String>,
+    // `HDR10`, `HLG` or `Dolby Vision`
+    pub hdr: Option<String>,
+    pub channels: Option<i32>,
+    pub sample_rate: Option<i32>,
+    pub channel_layout: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::media::Entity",
+        from = "Column::MediaId",
+        to = "super::media::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Media,
+}
+
+impl Related<super::media::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Media.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
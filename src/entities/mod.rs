@@ -0,0 +1,10 @@
+pub mod prelude;
+
+pub mod artwork;
+pub mod episode;
+pub mod item;
+pub mod library;
+pub mod media;
+pub mod media_stream;
+pub mod season;
+pub mod series;
@@ -0,0 +1,8 @@
+pub use super::artwork::Entity as Artwork;
+pub use super::episode::Entity as Episode;
+pub use super::item::Entity as Item;
+pub use super::library::Entity as Library;
+pub use super::media::Entity as Media;
+pub use super::media_stream::Entity as MediaStream;
+pub use super::season::Entity as Season;
+pub use super::series::Entity as Series;
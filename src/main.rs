@@ -1,19 +1,31 @@
 extern crate core;
 
+mod entities;
 mod models;
+mod parsers;
 mod routes;
 mod services;
+mod state;
 
+use crate::routes::item::{get_item, get_items, stream_item};
+use crate::routes::library::{
+    delete_library, get_libraries, post_library, scan_library, scan_one_library,
+};
 use crate::routes::media::{
-    get_file, get_media, get_media_info, post_media, stream_media, transcode_media,
-    transcode_subtitles,
+    get_duplicates, get_file, get_media, get_media_by_id, get_media_frame, get_media_image,
+    get_media_info, get_trickplay_sheet, get_trickplay_vtt, mark_watched, post_media, stream_media,
+    transcode_media, transcode_subtitles, unmark_watched,
 };
+use crate::routes::search::search;
+use crate::routes::series::{get_episodes, get_seasons, get_series};
+use crate::services::{ensure_default_library, generate_trickplay_previews, watch_library};
+use crate::state::AppState;
 use axum::http::Method;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use axum::Router;
 use dotenv::dotenv;
-use futures::executor::block_on;
-use sea_orm::{Database, DbErr};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection, DbErr};
 use std::env;
 use tower_http::cors::{Any, CorsLayer};
 
@@ -24,35 +36,69 @@ pub fn cors() -> CorsLayer {
         .allow_origin(Any)
 }
 
-async fn run() -> Result<(), DbErr> {
+async fn run() -> Result<DatabaseConnection, DbErr> {
     let database_url =
         env::var("DATABASE_URL").expect("Environment variable DATABASE_URL is required");
 
-    let _db = Database::connect(database_url);
+    let db = Database::connect(database_url).await?;
+    Migrator::up(&db, None).await?;
+    ensure_default_library(&db).await?;
 
-    Ok(())
+    Ok(db)
 }
 
-pub fn create_routes() -> Router {
+pub fn create_routes(state: AppState) -> Router {
     Router::new()
         .route("/medias", get(get_media))
         .route("/medias", post(post_media))
-        .route("/medias/get_file", post(get_file))
-        .route("/medias/stream", get(stream_media))
-        .route("/medias/info", get(get_media_info))
+        .route("/medias/:id", get(get_media_by_id))
+        .route("/medias/:id/file", get(get_file))
+        .route("/medias/:id/stream", get(stream_media))
+        .route("/medias/:id/info", get(get_media_info))
+        .route("/medias/:id/images/:kind", get(get_media_image))
+        .route("/medias/:id/frame", get(get_media_frame))
+        .route("/medias/:id/trickplay.vtt", get(get_trickplay_vtt))
+        .route("/medias/:id/trickplay/:sheet", get(get_trickplay_sheet))
+        .route(
+            "/medias/:id/watched",
+            post(mark_watched).delete(unmark_watched),
+        )
+        .route("/medias/duplicates", get(get_duplicates))
         .route("/medias/transcode", get(transcode_media))
         .route("/medias/transcode-subtitle", get(transcode_subtitles))
+        .route("/items", get(get_items))
+        .route("/items/:id", get(get_item))
+        .route("/items/:id/stream", get(stream_item))
+        .route("/library/scan", post(scan_library))
+        .route("/libraries", get(get_libraries))
+        .route("/libraries", post(post_library))
+        .route("/libraries/:id", delete(delete_library))
+        .route("/libraries/:id/scan", post(scan_one_library))
+        .route("/search", get(search))
+        .route("/series", get(get_series))
+        .route("/series/:id/seasons", get(get_seasons))
+        .route(
+            "/series/:id/seasons/:season_number/episodes",
+            get(get_episodes),
+        )
+        .with_state(state)
 }
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
-    if let Err(err) = block_on(run()) {
-        panic!("{:?}", err);
+    let db = match run().await {
+        Ok(db) => db,
+        Err(err) => panic!("{:?}", err),
     };
 
-    let app = Router::new().merge(create_routes()).layer(cors());
+    let state = AppState::new(db);
+
+    tokio::spawn(watch_library(state.clone()));
+    tokio::spawn(generate_trickplay_previews(state.clone()));
+
+    let app = Router::new().merge(create_routes(state)).layer(cors());
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
         .await
@@ -0,0 +1,26 @@
+// Bounding box for a resized image, the aspect ratio is always kept
+#[derive(serde::Deserialize)]
+pub struct ImageQuery {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameFormat {
+    #[default]
+    #[serde(alias = "jpg")]
+    Jpeg,
+    Png,
+    Webp,
+}
+
+#[derive(serde::Deserialize)]
+pub struct FrameQuery {
+    // Seconds from the start of the video
+    #[serde(default)]
+    pub t: f64,
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub format: FrameFormat,
+}
@@ -0,0 +1,127 @@
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+
+// Same shape as `ffprobe -show_format -show_streams -print_format json`,
+// ffprobe prints most numbers as strings and omits unknown values.
+#[derive(Serialize)]
+pub struct FfprobeOutput {
+    pub streams: Vec<FfprobeStream>,
+    pub format: FfprobeFormat,
+}
+
+#[derive(Serialize)]
+pub struct FfprobeStream {
+    pub index: i32,
+    pub codec_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codec_long_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codec_type: Option<String>,
+    pub codec_tag_string: String,
+    pub codec_tag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_aspect_ratio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pix_fmt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_range: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_space: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_transfer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_primaries: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_fmt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_layout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bits_per_sample: Option<i32>,
+    pub r_frame_rate: String,
+    pub avg_frame_rate: String,
+    pub time_base: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_pts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_rate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nb_frames: Option<String>,
+    pub disposition: FfprobeDisposition,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct FfprobeFormat {
+    pub filename: String,
+    pub nb_streams: u32,
+    pub nb_programs: u32,
+    pub format_name: String,
+    pub format_long_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    pub size: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_rate: Option<String>,
+    pub probe_score: i32,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+// AV_DISPOSITION_* bits, in the order ffprobe prints them
+const DISPOSITION_FLAGS: [(&str, i32); 18] = [
+    ("default", 1 << 0),
+    ("dub", 1 << 1),
+    ("original", 1 << 2),
+    ("comment", 1 << 3),
+    ("lyrics", 1 << 4),
+    ("karaoke", 1 << 5),
+    ("forced", 1 << 6),
+    ("hearing_impaired", 1 << 7),
+    ("visual_impaired", 1 << 8),
+    ("clean_effects", 1 << 9),
+    ("attached_pic", 1 << 10),
+    ("timed_thumbnails", 1 << 11),
+    ("non_diegetic", 1 << 12),
+    ("captions", 1 << 16),
+    ("descriptions", 1 << 17),
+    ("metadata", 1 << 18),
+    ("dependent", 1 << 19),
+    ("still_image", 1 << 20),
+];
+
+// Raw `AVStream.disposition` bit field, serialized as ffprobe's 0/1 object
+pub struct FfprobeDisposition(pub i32);
+
+impl Serialize for FfprobeDisposition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(DISPOSITION_FLAGS.len()))?;
+
+        for (name, flag) in DISPOSITION_FLAGS {
+            map.serialize_entry(name, &i32::from(self.0 & flag != 0))?;
+        }
+
+        map.end()
+    }
+}
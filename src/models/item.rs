@@ -0,0 +1,125 @@
+use crate::entities::item::ItemKind;
+use crate::entities::{item, media, media_stream};
+
+#[derive(serde::Serialize)]
+pub struct ItemSummary {
+    pub id: i32,
+    pub kind: ItemKind,
+    pub title: String,
+    pub year: Option<i32>,
+    pub series_name: Option<String>,
+    pub season_number: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub version_count: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct ItemDetail {
+    pub id: i32,
+    pub kind: ItemKind,
+    pub title: String,
+    pub year: Option<i32>,
+    pub series_name: Option<String>,
+    pub season_number: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub versions: Vec<VersionItem>,
+}
+
+#[derive(serde::Serialize)]
+pub struct AudioTrack {
+    pub codec: String,
+    pub language: Option<String>,
+    pub channels: Option<i32>,
+}
+
+// A media file of an item, described from its probed streams
+#[derive(serde::Serialize)]
+pub struct VersionItem {
+    // The media id, accepted by the stream and transcode endpoints
+    pub id: i32,
+    pub path: String,
+    pub size: i64,
+    pub container: Option<String>,
+    pub duration: Option<f64>,
+    pub resolution: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub video_codec: Option<String>,
+    pub hdr: Option<String>,
+    pub audio: Vec<AudioTrack>,
+    pub source: Option<String>,
+    pub release_group: Option<String>,
+    pub missing: bool,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct VersionQuery {
+    // Media id of the version, the best available one when omitted
+    pub version: Option<i32>,
+}
+
+// Classified on the width or the height, whichever reaches a tier, so that
+// cropped 1920x800 releases and 1440x1080 ones both count as 1080p
+pub fn resolution_label(width: i32, height: i32) -> String {
+    match (width, height) {
+        (w, h) if w >= 3200 || h >= 2000 => "2160p",
+        (w, h) if w >= 1800 || h >= 1000 => "1080p",
+        (w, h) if w >= 1200 || h >= 700 => "720p",
+        _ => "SD",
+    }
+    .to_string()
+}
+
+impl VersionItem {
+    pub fn new(media: media::Model, streams: &[media_stream::Model]) -> Self {
+        let video = streams.iter().find(|stream| stream.medium == "Video");
+
+        let (width, height) = match video {
+            Some(video) => (video.width, video.height),
+            None => (None, None),
+        };
+
+        VersionItem {
+            id: media.id,
+            path: media.path,
+            size: media.size,
+            container: media.container,
+            duration: media.duration,
+            resolution: width
+                .zip(height)
+                .map(|(width, height)| resolution_label(width, height))
+                .or(media.resolution),
+            width,
+            height,
+            video_codec: video.map(|video| video.codec.clone()),
+            hdr: video.and_then(|video| video.hdr.clone()),
+            audio: streams
+                .iter()
+                .filter(|stream| stream.medium == "Audio")
+                .map(|stream| AudioTrack {
+                    codec: stream.codec.clone(),
+                    language: stream.language.clone(),
+                    channels: stream.channels,
+                })
+                .collect(),
+            source: media.source,
+            release_group: media.release_group,
+            missing: media.missing,
+        }
+    }
+}
+
+impl ItemDetail {
+    pub fn new(item: item::Model, versions: Vec<VersionItem>) -> Self {
+        ItemDetail {
+            id: item.id,
+            kind: item.kind,
+            title: item.title,
+            year: item.year,
+            series_name: item.series_name,
+            season_number: item.season_number,
+            episode_number: item.episode_number,
+            versions,
+        }
+    }
+}
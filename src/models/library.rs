@@ -0,0 +1,42 @@
+use crate::entities::library;
+use crate::entities::library::{LibraryKind, ScanOptions};
+use chrono::NaiveDateTime;
+
+#[derive(serde::Serialize, Default)]
+pub struct ScanReport {
+    pub added: u32,
+    pub updated: u32,
+    pub removed: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct LibraryItem {
+    pub id: i32,
+    pub name: String,
+    pub kind: LibraryKind,
+    pub roots: Vec<String>,
+    pub scan_options: ScanOptions,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<library::Model> for LibraryItem {
+    fn from(model: library::Model) -> Self {
+        LibraryItem {
+            id: model.id,
+            name: model.name,
+            kind: model.kind,
+            roots: model.roots.0,
+            scan_options: model.scan_options,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateLibrary {
+    pub name: String,
+    pub kind: LibraryKind,
+    pub roots: Vec<String>,
+    #[serde(default)]
+    pub scan_options: ScanOptions,
+}
@@ -1,30 +1,314 @@
+use crate::entities::media::{Chapter, Rating};
+use crate::entities::{media, media_stream};
 use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct MediaItem {
-    pub id: u32,
+    pub id: i32,
     pub title: String,
     pub path: String,
     pub created_at: NaiveDateTime,
+    pub size: i64,
+    pub container: Option<String>,
+    pub duration: Option<f64>,
+    pub missing: bool,
+    pub series_name: Option<String>,
+    pub season_number: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub episode_number_end: Option<i32>,
+    pub year: Option<i32>,
+    pub source: Option<String>,
+    pub resolution: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub languages: Vec<String>,
+    pub release_group: Option<String>,
+    pub library_id: Option<i32>,
+    pub item_id: Option<i32>,
+    pub watched: bool,
+    pub overview: Option<String>,
+    pub cast: Vec<String>,
+    pub original_title: Option<String>,
+    pub genres: Vec<String>,
+    pub studios: Vec<String>,
+    pub ratings: Vec<Rating>,
+    pub runtime: Option<i32>,
+    pub external_ids: BTreeMap<String, String>,
+    pub display_title: String,
+}
+
+// Comma separated columns such as `languages` or `cast_members`
+fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// "Kingsman The Secret Service (2014) — 1080p WEB-DL"
+fn display_title(model: &media::Model) -> String {
+    let mut display = match model.year {
+        Some(year) => format!("{} ({})", model.title, year),
+        None => model.title.clone(),
+    };
+
+    let quality: Vec<&str> = [&model.resolution, &model.source]
+        .into_iter()
+        .filter_map(|tag| tag.as_deref())
+        .collect();
+
+    if !quality.is_empty() {
+        display.push_str(" — ");
+        display.push_str(&quality.join(" "));
+    }
+
+    display
+}
+
+impl From<media::Model> for MediaItem {
+    fn from(model: media::Model) -> Self {
+        let display_title = display_title(&model);
+        let languages = split_list(model.languages.as_deref());
+        let cast = split_list(model.cast_members.as_deref());
+        let genres = split_list(model.genres.as_deref());
+        let studios = split_list(model.studios.as_deref());
+
+        MediaItem {
+            id: model.id,
+            title: model.title,
+            path: model.path,
+            created_at: model.created_at,
+            size: model.size,
+            container: model.container,
+            duration: model.duration,
+            missing: model.missing,
+            series_name: model.series_name,
+            season_number: model.season_number,
+            episode_number: model.episode_number,
+            episode_number_end: model.episode_number_end,
+            year: model.year,
+            source: model.source,
+            resolution: model.resolution,
+            video_codec: model.video_codec,
+            audio_codec: model.audio_codec,
+            languages,
+            release_group: model.release_group,
+            library_id: model.library_id,
+            item_id: model.item_id,
+            watched: model.watched,
+            overview: model.overview,
+            cast,
+            original_title: model.original_title,
+            genres,
+            studios,
+            ratings: model.ratings.map(|ratings| ratings.0).unwrap_or_default(),
+            runtime: model.runtime,
+            external_ids: model.external_ids.map(|ids| ids.0).unwrap_or_default(),
+            display_title,
+        }
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct CreateMediaItem {
     pub title: String,
     pub path: String,
+    pub library_id: Option<i32>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaSort {
+    #[default]
+    Id,
+    Title,
+    CreatedAt,
+    Year,
+    Duration,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct MediaQuery {
+    // 1-based page number
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+    #[serde(default)]
+    pub sort: MediaSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub library: Option<i32>,
+    pub resolution: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_language: Option<String>,
+    pub watched: Option<bool>,
+}
+
+#[derive(serde::Serialize)]
+pub struct MediaPage {
+    pub items: Vec<MediaItem>,
+    pub page: u64,
+    pub limit: u64,
+    pub total_items: u64,
+    pub total_pages: u64,
+}
+
+#[derive(serde::Serialize)]
+pub struct DuplicateFile {
+    pub id: i32,
+    pub title: String,
+    pub path: String,
+    pub size: i64,
+    pub library_id: Option<i32>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DuplicateGroup {
+    pub fingerprint: String,
+    pub size: i64,
+    pub files: Vec<DuplicateFile>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaInfoFormat {
+    #[default]
+    Json,
+    // The JSON layout of `ffprobe -show_format -show_streams`
+    Ffprobe,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct MediaInfoQuery {
+    #[serde(default)]
+    pub format: MediaInfoFormat,
 }
 
 #[derive(serde::Serialize)]
 pub struct MediaInfo {
     pub name: String,
+    pub container: ContainerInfo,
     pub codecs: Vec<CodecInfo>,
 }
 
+#[derive(serde::Serialize)]
+pub struct ContainerInfo {
+    pub format_name: Option<String>,
+    // Seconds
+    pub duration: Option<f64>,
+    pub size: i64,
+    pub bit_rate: Option<i64>,
+    pub start_time: Option<f64>,
+    pub tags: BTreeMap<String, String>,
+    pub chapters: Vec<Chapter>,
+}
+
+impl From<&media::Model> for ContainerInfo {
+    fn from(model: &media::Model) -> Self {
+        ContainerInfo {
+            format_name: model.container.clone(),
+            duration: model.duration,
+            size: model.size,
+            bit_rate: model.bit_rate,
+            start_time: model.start_time,
+            tags: model.tags.clone().unwrap_or_default().0,
+            chapters: model.chapters.clone().unwrap_or_default().0,
+        }
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct CodecInfo {
+    pub index: i32,
     pub codec_id: String,
     pub codec_medium: String,
-    pub length: i64,
-    // pub format: i    32,
-    // pub bit_rate: i64,
+    // Seconds
+    pub duration: Option<f64>,
+    pub bit_rate: Option<i64>,
+    pub profile: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub default: bool,
+    pub forced: bool,
+    pub hearing_impaired: bool,
+    pub video: Option<VideoInfo>,
+    pub audio: Option<AudioInfo>,
+}
+
+#[derive(serde::Serialize)]
+pub struct VideoInfo {
+    pub width: i32,
+    pub height: i32,
+    pub pixel_format: Option<String>,
+    pub frame_rate: Option<f64>,
+    pub level: Option<i32>,
+    pub color_primaries: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_space: Option<String>,
+    pub hdr: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct AudioInfo {
+    pub sample_rate: Option<i32>,
+    pub channels: Option<i32>,
+    pub channel_layout: Option<String>,
+}
+
+impl From<media_stream::Model> for CodecInfo {
+    fn from(stream: media_stream::Model) -> Self {
+        let video = match stream.medium.as_str() {
+            "Video" => Some(VideoInfo {
+                width: stream.width.unwrap_or_default(),
+                height: stream.height.unwrap_or_default(),
+                pixel_format: stream.pixel_format,
+                frame_rate: stream.frame_rate,
+                level: stream.level,
+                color_primaries: stream.color_primaries,
+                color_transfer: stream.color_transfer,
+                color_space: stream.color_space,
+                hdr: stream.hdr,
+            }),
+            _ => None,
+        };
+
+        let audio = match stream.medium.as_str() {
+            "Audio" => Some(AudioInfo {
+                sample_rate: stream.sample_rate,
+                channels: stream.channels,
+                channel_layout: stream.channel_layout,
+            }),
+            _ => None,
+        };
+
+        CodecInfo {
+            index: stream.stream_index,
+            codec_id: stream.codec,
+            codec_medium: stream.medium,
+            duration: stream.duration,
+            bit_rate: stream.bit_rate,
+            profile: stream.profile,
+            language: stream.language,
+            title: stream.title,
+            default: stream.is_default,
+            forced: stream.is_forced,
+            hearing_impaired: stream.is_hearing_impaired,
+            video,
+            audio,
+        }
+    }
 }
@@ -1,3 +1,15 @@
+pub mod artwork;
+pub mod ffprobe;
+pub mod item;
+pub mod library;
 pub mod media;
+pub mod search;
+pub mod series;
 
+pub use artwork::*;
+pub use ffprobe::*;
+pub use item::*;
+pub use library::*;
 pub use media::*;
+pub use search::*;
+pub use series::*;
@@ -0,0 +1,13 @@
+use crate::models::MediaItem;
+
+#[derive(serde::Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SearchResult {
+    pub score: f64,
+    pub media: MediaItem,
+}
@@ -0,0 +1,34 @@
+use crate::models::MediaItem;
+
+#[derive(serde::Serialize)]
+pub struct SeriesItem {
+    pub id: i32,
+    pub name: String,
+    pub season_count: i64,
+    pub episode_count: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct SeasonItem {
+    pub id: i32,
+    pub series_id: i32,
+    pub season_number: i32,
+    pub name: String,
+    pub episode_count: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct EpisodeItem {
+    pub id: i32,
+    pub season_number: i32,
+    pub episode_number: i32,
+    pub episode_number_end: Option<i32>,
+    pub media: MediaItem,
+}
+
+pub fn season_name(season_number: i32) -> String {
+    match season_number {
+        0 => "Specials".to_string(),
+        number => format!("Season {}", number),
+    }
+}
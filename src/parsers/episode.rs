@@ -0,0 +1,241 @@
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpisodeInfo {
+    pub series: String,
+    pub season: i32,
+    pub episode: i32,
+    // Last episode contained in multi-episode files such as S01E01-E02
+    pub episode_end: Option<i32>,
+}
+
+// Foundation.S02E03, Show S01E01E02, Show - S01E01-E02, Show.S01E01-02,
+// Show.S01E01E02E03. The end of a chain is the last episode of the file.
+fn season_episode_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"(?i)^(?P<series>.*?)[\s._\-\[(]*\bS(?P<season>\d{1,3})[\s._-]?E(?P<episode>\d{1,4})(?:(?:[\s._-]?-?[\s._-]?E|-)(?P<end>\d{1,4}))*\b",
+        )
+        .unwrap()
+    })
+}
+
+// Show 1x03, Show.2x03-2x04, Show 1x03x04, Show 1x03x04x05
+fn cross_notation_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"(?i)^(?P<series>.*?)[\s._\-\[(]*\b(?P<season>\d{1,2})x(?P<episode>\d{2,3})(?:(?:-\d{1,2}x|x|-)(?P<end>\d{2,3}))*\b",
+        )
+        .unwrap()
+    })
+}
+
+// Folders such as `Season 02`, `Saison 1`, `S03` or `Specials`
+fn season_folder_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)^(?:(?:season|saison|series|s)\s*\d{1,3}|specials)$").unwrap()
+    })
+}
+
+// A year closing the series name, `Planet Earth II (2016)` or `Doctor.Who.2005`
+fn series_year_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"^(?P<name>.+?)(?:[\s._-]*[(\[](?:19|20)\d{2}[)\]]|[\s._-]+(?:19|20)\d{2})[\s._-]*$",
+        )
+        .unwrap()
+    })
+}
+
+pub fn is_season_folder(name: &str) -> bool {
+    season_folder_regex().is_match(name)
+}
+
+// Dots separate the words of scene names only, names already written with
+// spaces keep theirs as in `Mr. Holland's Opus`
+pub fn clean_name(raw: &str) -> String {
+    let raw = if raw.trim().contains(' ') {
+        raw.replace('_', " ")
+    } else {
+        raw.replace(['.', '_'], " ")
+    };
+
+    raw.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| matches!(c, '-' | '[' | '(' | '.') || c.is_whitespace())
+        .to_string()
+}
+
+// The year only tells remakes apart, the episodes of `Planet Earth II (2016)`
+// and of the `Planet Earth II` folder belong to the same series. A name that
+// is nothing but a year, such as `1883`, is kept.
+fn series_name(raw: &str) -> String {
+    let name = series_year_regex()
+        .captures(raw.trim())
+        .and_then(|captures| captures.name("name"))
+        .map_or(raw, |name| name.as_str());
+
+    clean_name(name)
+}
+
+// When the file name carries no series name (`S01E02.mkv`) use the folder it lives in
+fn series_from_folders(path: &Path) -> Option<String> {
+    path.ancestors()
+        .skip(1)
+        .filter_map(|ancestor| ancestor.file_name()?.to_str())
+        .find(|name| !is_season_folder(name))
+        .map(series_name)
+        .filter(|name| !name.is_empty())
+}
+
+// End of the `S01E02` or `1x02` marker, release tags only come after it
+pub fn episode_marker_end(stem: &str) -> Option<usize> {
+    season_episode_regex()
+        .find(stem)
+        .or_else(|| cross_notation_regex().find(stem))
+        .map(|marker| marker.end())
+}
+
+pub fn parse_episode(path: &Path) -> Option<EpisodeInfo> {
+    let stem = path.file_stem()?.to_str()?;
+
+    let captures = season_episode_regex()
+        .captures(stem)
+        .or_else(|| cross_notation_regex().captures(stem))?;
+
+    let season = captures["season"].parse::<i32>().ok()?;
+    let episode = captures["episode"].parse::<i32>().ok()?;
+    let episode_end = captures
+        .name("end")
+        .and_then(|end| end.as_str().parse::<i32>().ok())
+        .filter(|end| *end > episode);
+
+    let series = Some(series_name(&captures["series"]))
+        .filter(|series| !series.is_empty())
+        .or_else(|| series_from_folders(path))?;
+
+    Some(EpisodeInfo {
+        series,
+        season,
+        episode,
+        episode_end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(path: &str) -> Option<(String, i32, i32, Option<i32>)> {
+        parse_episode(Path::new(path)).map(|episode| {
+            (
+                episode.series,
+                episode.season,
+                episode.episode,
+                episode.episode_end,
+            )
+        })
+    }
+
+    #[test]
+    fn season_episode_notation() {
+        for (path, series, season, number, end) in [
+            ("Foundation.S02E03.mkv", "Foundation", 2, 3, None),
+            (
+                "Foundation.s02e03.1080p.WEB-DL.mkv",
+                "Foundation",
+                2,
+                3,
+                None,
+            ),
+            ("The Show S01E01E02.mkv", "The Show", 1, 1, Some(2)),
+            ("The Show - S01E01-E02.mkv", "The Show", 1, 1, Some(2)),
+            ("The.Show.S01E01-02.mkv", "The Show", 1, 1, Some(2)),
+            ("The.Show.S01E01E02E03.mkv", "The Show", 1, 1, Some(3)),
+            (
+                "The.Show.S01E01-E02-E03.720p.mkv",
+                "The Show",
+                1,
+                1,
+                Some(3),
+            ),
+            ("The Show S01 E05.mkv", "The Show", 1, 5, None),
+            ("Mr. Robot S01E01.mkv", "Mr. Robot", 1, 1, None),
+            // The year of the series is not part of its name
+            (
+                "Planet Earth II (2016) S01E01 Islands.mkv",
+                "Planet Earth II",
+                1,
+                1,
+                None,
+            ),
+            ("Doctor.Who.2005.S01E01.mkv", "Doctor Who", 1, 1, None),
+            ("1883.S01E01.mkv", "1883", 1, 1, None),
+        ] {
+            assert_eq!(
+                episode(path),
+                Some((series.to_string(), season, number, end)),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn cross_notation() {
+        for (path, series, season, number, end) in [
+            ("The Show 1x03.mkv", "The Show", 1, 3, None),
+            ("The.Show.2x03-2x04.mkv", "The Show", 2, 3, Some(4)),
+            ("The Show 1x03x04.mkv", "The Show", 1, 3, Some(4)),
+            ("The Show 1x03x04x05.mkv", "The Show", 1, 3, Some(5)),
+        ] {
+            assert_eq!(
+                episode(path),
+                Some((series.to_string(), season, number, end)),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn series_from_the_folders() {
+        assert_eq!(
+            episode("/shows/The Show/Season 02/S02E05.mkv"),
+            Some((String::from("The Show"), 2, 5, None))
+        );
+        assert_eq!(
+            episode("/shows/The.Show/S01E01.mkv"),
+            Some((String::from("The Show"), 1, 1, None))
+        );
+        assert_eq!(
+            episode("/shows/Planet Earth II (2016)/Season 01/S01E02.mkv"),
+            Some((String::from("Planet Earth II"), 1, 2, None))
+        );
+    }
+
+    #[test]
+    fn not_episodes() {
+        for path in ["The Matrix (1999).mkv", "Movie.1080p.x264.mkv", "S01.mkv"] {
+            assert_eq!(episode(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn season_folders() {
+        for name in ["Season 02", "season 1", "Saison 1", "S03", "Specials"] {
+            assert!(is_season_folder(name), "{}", name);
+        }
+
+        for name in ["The Show", "Seasons Greetings", "Season"] {
+            assert!(!is_season_folder(name), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub mod episode;
+pub mod movie;
+pub mod nfo;
+pub mod release;
+
+pub use episode::*;
+pub use movie::*;
+pub use nfo::*;
+pub use release::*;
@@ -0,0 +1,151 @@
+use crate::parsers::{clean_name, normalize_separators, release_tags_start};
+use regex::{Match, Regex};
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovieInfo {
+    pub title: String,
+    pub year: Option<i32>,
+}
+
+fn year_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?:19|20)\d{2}").unwrap())
+}
+
+// `-10-23` or `.10.23` after a year, the rest of a date
+fn month_day_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"^[-.](?:0[1-9]|1[0-2])[-.](?:0[1-9]|[12]\d|3[01])(?:[^0-9]|$)").unwrap()
+    })
+}
+
+fn is_year_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '.' | '-' | '_' | '[' | ']' | '(' | ')')
+}
+
+// The last standalone year preceded by a title. It closes the title, so
+// `Blade Runner 2049 (2017)` keeps 2049 in it and words such as `French` or
+// `Web` before the year are never taken for release tags. The year of a date
+// such as `Enregistrement 2024-10-23 165821` is not a release year.
+pub fn find_title_year(stem: &str) -> Option<Match<'_>> {
+    year_regex()
+        .find_iter(stem)
+        .filter(|year| {
+            let before = stem[..year.start()].chars().next_back();
+            let after = stem[year.end()..].chars().next();
+
+            before.is_some_and(is_year_separator)
+                && after.is_none_or(is_year_separator)
+                && !month_day_regex().is_match(&stem[year.end()..])
+        })
+        .filter(|year| !clean_name(&stem[..year.start()]).is_empty())
+        .last()
+}
+
+pub fn parse_movie(path: &Path) -> Option<MovieInfo> {
+    let stem = normalize_separators(path.file_stem()?.to_str()?);
+
+    if let Some(year) = find_title_year(&stem) {
+        return Some(MovieInfo {
+            title: clean_name(&stem[..year.start()]),
+            year: year.as_str().parse::<i32>().ok(),
+        });
+    }
+
+    // Without a year the title ends where the release tags start
+    let title_part = match release_tags_start(&stem) {
+        Some(start) if start > 0 => &stem[..start],
+        _ => stem.as_str(),
+    };
+
+    Some(MovieInfo {
+        title: clean_name(title_part),
+        year: None,
+    })
+    .filter(|movie| !movie.title.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movie(file_name: &str) -> Option<(String, Option<i32>)> {
+        parse_movie(Path::new(file_name)).map(|movie| (movie.title, movie.year))
+    }
+
+    #[test]
+    fn title_and_year() {
+        for (file_name, title, year) in [
+            (
+                "The.Matrix.1999.1080p.BluRay.x264-GRP.mkv",
+                "The Matrix",
+                Some(1999),
+            ),
+            ("The Matrix (1999).mkv", "The Matrix", Some(1999)),
+            ("The_Matrix_1999_720p.mkv", "The Matrix", Some(1999)),
+            (
+                "Blade Runner 2049 (2017).mkv",
+                "Blade Runner 2049",
+                Some(2017),
+            ),
+            (
+                "Blade.Runner.2049.2017.2160p.UHD.mkv",
+                "Blade Runner 2049",
+                Some(2017),
+            ),
+            ("1917 (2019).mkv", "1917", Some(2019)),
+            (
+                "2001.A.Space.Odyssey.1968.mkv",
+                "2001 A Space Odyssey",
+                Some(1968),
+            ),
+            (
+                "The French Connection (1971) 1080p BluRay.mkv",
+                "The French Connection",
+                Some(1971),
+            ),
+            ("Charlotte's Web (2006).mkv", "Charlotte's Web", Some(2006)),
+            (
+                "Mr. Holland's Opus (1995).mkv",
+                "Mr. Holland's Opus",
+                Some(1995),
+            ),
+            ("Movie [2010] [1080p].mkv", "Movie", Some(2010)),
+        ] {
+            assert_eq!(
+                movie(file_name),
+                Some((title.to_string(), year)),
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn title_without_a_year() {
+        for (file_name, title) in [
+            ("Some.Movie.1080p.WEB-DL.mkv", "Some Movie"),
+            ("Home Movie.mkv", "Home Movie"),
+            // A lone year is the title
+            ("2012.mkv", "2012"),
+            // Digits glued to other characters are not a year
+            ("Movie.x2019.mkv", "Movie x2019"),
+            // Nor is the year of a date
+            (
+                "Enregistrement 2024-10-23 165821.mp4",
+                "Enregistrement 2024-10-23 165821",
+            ),
+            ("Party.2019.12.31.mkv", "Party 2019 12 31"),
+        ] {
+            assert_eq!(
+                movie(file_name),
+                Some((title.to_string(), None)),
+                "{}",
+                file_name
+            );
+        }
+    }
+}
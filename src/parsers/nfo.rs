@@ -0,0 +1,338 @@
+use roxmltree::{Document, Node};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NfoKind {
+    Movie,
+    TvShow,
+    Episode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NfoRating {
+    // `imdb`, `tmdb`, ... or `default` for a bare `<rating>` value
+    pub name: String,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub votes: Option<i64>,
+}
+
+// Local metadata from a Kodi style `movie.nfo`, `tvshow.nfo` or episode NFO file
+#[derive(Debug, Clone, PartialEq)]
+pub struct NfoInfo {
+    pub kind: NfoKind,
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    pub year: Option<i32>,
+    pub plot: Option<String>,
+    pub genres: Vec<String>,
+    pub studios: Vec<String>,
+    pub cast: Vec<String>,
+    pub ratings: Vec<NfoRating>,
+    // Minutes
+    pub runtime: Option<i32>,
+    // External ids keyed by provider: `imdb`, `tmdb`, `tvdb`
+    pub ids: BTreeMap<String, String>,
+    pub season: Option<i32>,
+    pub episode: Option<i32>,
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn text(node: Node, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|child| child.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+// Repeated elements such as `<genre>`, some tools also pack several values in one
+fn texts(node: Node, name: &str) -> Vec<String> {
+    node.children()
+        .filter(|child| child.has_tag_name(name))
+        .filter_map(|child| child.text())
+        .flat_map(|text| text.split(" / "))
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn number<T: std::str::FromStr>(node: Node, name: &str) -> Option<T> {
+    text(node, name).and_then(|text| text.parse().ok())
+}
+
+fn parse_year(root: Node) -> Option<i32> {
+    number(root, "year")
+        .or_else(|| {
+            ["premiered", "aired"]
+                .iter()
+                .filter_map(|name| text(root, name))
+                .find_map(|date| date.get(..4)?.parse().ok())
+        })
+        .filter(|year| *year > 0)
+}
+
+// `<ratings><rating name="imdb" max="10"><value>7.7</value>...` or a bare `<rating>7.7</rating>`
+fn parse_ratings(root: Node) -> Vec<NfoRating> {
+    if let Some(ratings) = child(root, "ratings") {
+        return ratings
+            .children()
+            .filter(|rating| rating.has_tag_name("rating"))
+            .filter_map(|rating| {
+                Some(NfoRating {
+                    name: rating.attribute("name").unwrap_or("default").to_string(),
+                    value: number(rating, "value")?,
+                    max: rating.attribute("max").and_then(|max| max.parse().ok()),
+                    votes: text(rating, "votes")
+                        .and_then(|votes| votes.replace(',', "").parse().ok()),
+                })
+            })
+            .collect();
+    }
+
+    number(root, "rating")
+        .map(|value| NfoRating {
+            name: "default".to_string(),
+            value,
+            max: None,
+            votes: text(root, "votes").and_then(|votes| votes.replace(',', "").parse().ok()),
+        })
+        .into_iter()
+        .collect()
+}
+
+fn parse_ids(root: Node) -> BTreeMap<String, String> {
+    let mut ids = BTreeMap::new();
+
+    // Older files only have a bare `<id>`, an IMDb id for movies
+    if let Some(id) = text(root, "id") {
+        let provider = if id.starts_with("tt") {
+            "imdb"
+        } else {
+            "default"
+        };
+        ids.insert(provider.to_string(), id);
+    }
+
+    for (name, provider) in [("imdbid", "imdb"), ("tmdbid", "tmdb"), ("tvdbid", "tvdb")] {
+        if let Some(id) = text(root, name) {
+            ids.insert(provider.to_string(), id);
+        }
+    }
+
+    for unique_id in root
+        .children()
+        .filter(|child| child.has_tag_name("uniqueid"))
+    {
+        if let (Some(provider), Some(id)) = (unique_id.attribute("type"), unique_id.text()) {
+            if !id.trim().is_empty() {
+                ids.insert(provider.to_lowercase(), id.trim().to_string());
+            }
+        }
+    }
+
+    ids
+}
+
+pub fn parse_nfo(xml: &str) -> Option<NfoInfo> {
+    // Some tools append the provider URL after the XML document
+    let xml = match xml.rfind('>') {
+        Some(end) => &xml[..=end],
+        None => xml,
+    };
+
+    let document = Document::parse(xml).ok()?;
+    let root = document.root_element();
+
+    let kind = match root.tag_name().name() {
+        "movie" => NfoKind::Movie,
+        "tvshow" => NfoKind::TvShow,
+        "episodedetails" => NfoKind::Episode,
+        _ => return None,
+    };
+
+    let cast = root
+        .children()
+        .filter(|child| child.has_tag_name("actor"))
+        .filter_map(|actor| text(actor, "name"))
+        .collect();
+
+    Some(NfoInfo {
+        kind,
+        title: text(root, "title"),
+        original_title: text(root, "originaltitle"),
+        year: parse_year(root),
+        plot: text(root, "plot").or_else(|| text(root, "outline")),
+        genres: texts(root, "genre"),
+        studios: texts(root, "studio"),
+        cast,
+        ratings: parse_ratings(root),
+        runtime: number(root, "runtime").filter(|runtime| *runtime > 0),
+        ids: parse_ids(root),
+        season: number(root, "season"),
+        episode: number(root, "episode"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movie() {
+        let nfo = parse_nfo(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+            <movie>
+                <title>Blade Runner 2049</title>
+                <originaltitle>Blade Runner 2049</originaltitle>
+                <year>2017</year>
+                <plot>Thirty years after the events of the first film...</plot>
+                <runtime>164</runtime>
+                <genre>Science Fiction</genre>
+                <genre>Drama</genre>
+                <studio>Alcon Entertainment</studio>
+                <actor><name>Ryan Gosling</name><role>K</role></actor>
+                <actor><name>Harrison Ford</name></actor>
+            </movie>"#,
+        )
+        .unwrap();
+
+        assert_eq!(nfo.kind, NfoKind::Movie);
+        assert_eq!(nfo.title.as_deref(), Some("Blade Runner 2049"));
+        assert_eq!(nfo.year, Some(2017));
+        assert_eq!(nfo.runtime, Some(164));
+        assert_eq!(nfo.genres, vec!["Science Fiction", "Drama"]);
+        assert_eq!(nfo.studios, vec!["Alcon Entertainment"]);
+        assert_eq!(nfo.cast, vec!["Ryan Gosling", "Harrison Ford"]);
+    }
+
+    #[test]
+    fn year_from_the_premiere_date() {
+        let nfo = parse_nfo("<movie><premiered>1999-03-31</premiered></movie>").unwrap();
+
+        assert_eq!(nfo.year, Some(1999));
+    }
+
+    #[test]
+    fn bare_rating() {
+        let nfo = parse_nfo("<movie><rating>7.7</rating><votes>1,234</votes></movie>").unwrap();
+
+        assert_eq!(
+            nfo.ratings,
+            vec![NfoRating {
+                name: String::from("default"),
+                value: 7.7,
+                max: None,
+                votes: Some(1_234),
+            }]
+        );
+    }
+
+    #[test]
+    fn ratings() {
+        let nfo = parse_nfo(
+            r#"<movie>
+                <rating>1.0</rating>
+                <ratings>
+                    <rating name="imdb" max="10" default="true">
+                        <value>8.0</value>
+                        <votes>600000</votes>
+                    </rating>
+                    <rating name="themoviedb" max="10"><value>7.5</value></rating>
+                    <rating name="broken"><votes>12</votes></rating>
+                </ratings>
+            </movie>"#,
+        )
+        .unwrap();
+
+        // `<ratings>` wins over the bare value, entries without a value are dropped
+        assert_eq!(
+            nfo.ratings,
+            vec![
+                NfoRating {
+                    name: String::from("imdb"),
+                    value: 8.0,
+                    max: Some(10.0),
+                    votes: Some(600_000),
+                },
+                NfoRating {
+                    name: String::from("themoviedb"),
+                    value: 7.5,
+                    max: Some(10.0),
+                    votes: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unique_ids() {
+        let nfo = parse_nfo(
+            r#"<movie>
+                <id>tt1856101</id>
+                <tmdbid>335984</tmdbid>
+                <uniqueid type="IMDB" default="true">tt1856101</uniqueid>
+                <uniqueid type="tvdb"> 12345 </uniqueid>
+                <uniqueid type="empty"></uniqueid>
+            </movie>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            nfo.ids,
+            BTreeMap::from([
+                (String::from("imdb"), String::from("tt1856101")),
+                (String::from("tmdb"), String::from("335984")),
+                (String::from("tvdb"), String::from("12345")),
+            ])
+        );
+    }
+
+    #[test]
+    fn trailing_provider_url() {
+        let nfo =
+            parse_nfo("<movie><title>Heat</title></movie>\nhttps://www.themoviedb.org/movie/949\n")
+                .unwrap();
+
+        assert_eq!(nfo.title.as_deref(), Some("Heat"));
+    }
+
+    #[test]
+    fn packed_lists() {
+        let nfo = parse_nfo(
+            "<tvshow><genre>Drama / Crime</genre><genre> Thriller </genre><studio>HBO / </studio></tvshow>",
+        )
+        .unwrap();
+
+        assert_eq!(nfo.kind, NfoKind::TvShow);
+        assert_eq!(nfo.genres, vec!["Drama", "Crime", "Thriller"]);
+        assert_eq!(nfo.studios, vec!["HBO"]);
+    }
+
+    #[test]
+    fn episode() {
+        let nfo = parse_nfo(
+            "<episodedetails><title>Pilot</title><season>1</season><episode>2</episode><aired>2008-01-20</aired></episodedetails>",
+        )
+        .unwrap();
+
+        assert_eq!(nfo.kind, NfoKind::Episode);
+        assert_eq!(nfo.season, Some(1));
+        assert_eq!(nfo.episode, Some(2));
+        assert_eq!(nfo.year, Some(2008));
+    }
+
+    #[test]
+    fn not_an_nfo() {
+        assert_eq!(
+            parse_nfo("<musicvideo><title>Song</title></musicvideo>"),
+            None
+        );
+        assert_eq!(parse_nfo("https://www.imdb.com/title/tt0113277/"), None);
+        assert_eq!(parse_nfo("<movie><title>Broken</movie>"), None);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::parsers::{episode_marker_end, find_title_year};
+use regex::Regex;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReleaseTags {
+    pub source: Option<String>,
+    pub resolution: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub languages: Vec<String>,
+    pub release_group: Option<String>,
+}
+
+// Each tag family is a list of (pattern, canonical name), the first matching
+// pattern wins so more specific spellings must come first.
+const SOURCES: [(&str, &str); 9] = [
+    (r"WEB[-. ]?DL", "WEB-DL"),
+    (r"WEB[-. ]?Rip", "WEBRip"),
+    (r"WEB", "WEB"),
+    (r"Blu[-. ]?Ray|BDRemux|Remux", "BluRay"),
+    (r"BDRip", "BDRip"),
+    (r"BRRip", "BRRip"),
+    (r"HDTV", "HDTV"),
+    (r"DVDRip", "DVDRip"),
+    (r"HDRip", "HDRip"),
+];
+
+const RESOLUTIONS: [(&str, &str); 6] = [
+    (r"2160p|4K|UHD", "2160p"),
+    (r"1080p", "1080p"),
+    (r"1080i", "1080i"),
+    (r"720p", "720p"),
+    (r"576p", "576p"),
+    (r"480p", "480p"),
+];
+
+const VIDEO_CODECS: [(&str, &str); 6] = [
+    (r"x265|h\.?265|HEVC", "H.265"),
+    (r"x264|h\.?264|AVC", "H.264"),
+    (r"AV1", "AV1"),
+    (r"VP9", "VP9"),
+    (r"XviD", "XviD"),
+    (r"DivX", "DivX"),
+];
+
+const AUDIO_CODECS: [(&str, &str); 9] = [
+    (r"TrueHD", "TrueHD"),
+    (r"DTS[-. ]?HD(?:[-. ]?MA)?", "DTS-HD MA"),
+    (r"DTS", "DTS"),
+    (r"E-?AC-?3|DDP(?:[ .]?[257]\.?[01])?|DD\+", "E-AC-3"),
+    (r"AC-?3|DD(?:[ .]?[257]\.?[01])?", "AC-3"),
+    (r"AAC(?:[ .]?[257]\.?[01])?", "AAC"),
+    (r"FLAC", "FLAC"),
+    (r"Opus", "Opus"),
+    (r"MP3", "MP3"),
+];
+
+const LANGUAGES: [(&str, &str); 9] = [
+    (r"MULTi", "MULTi"),
+    (r"VOSTFR", "VOSTFR"),
+    (r"SUBFRENCH", "SUBFRENCH"),
+    (r"TRUEFRENCH", "TRUEFRENCH"),
+    (r"FRENCH", "FRENCH"),
+    (r"VFF", "VFF"),
+    (r"VFQ", "VFQ"),
+    (r"VF", "VF"),
+    (r"VO", "VO"),
+];
+
+struct TagFamily {
+    patterns: Vec<(Regex, &'static str)>,
+}
+
+impl TagFamily {
+    fn new(patterns: &[(&str, &'static str)]) -> Self {
+        TagFamily {
+            patterns: patterns
+                .iter()
+                .map(|(pattern, name)| {
+                    // Tags are whole tokens between separators, `\b` would also
+                    // split words on apostrophes
+                    let regex = format!(
+                        r"(?i)(?:^|[\s.\-\[\](),+])(?P<tag>{})(?:$|[\s.\-\[\](),+])",
+                        pattern
+                    );
+                    (Regex::new(&regex).unwrap(), *name)
+                })
+                .collect(),
+        }
+    }
+
+    fn first(&self, text: &str) -> Option<String> {
+        self.patterns
+            .iter()
+            .find(|(regex, _)| regex.is_match(text))
+            .map(|(_, name)| name.to_string())
+    }
+
+    fn all(&self, text: &str) -> Vec<String> {
+        self.patterns
+            .iter()
+            .filter(|(regex, _)| regex.is_match(text))
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+
+    // Byte offset of the earliest tag of this family in `text`
+    fn earliest(&self, text: &str) -> Option<usize> {
+        self.patterns
+            .iter()
+            .filter_map(|(regex, _)| Some(regex.captures(text)?.name("tag")?.start()))
+            .min()
+    }
+}
+
+struct TagFamilies {
+    sources: TagFamily,
+    resolutions: TagFamily,
+    video_codecs: TagFamily,
+    audio_codecs: TagFamily,
+    languages: TagFamily,
+}
+
+impl TagFamilies {
+    fn iter(&self) -> impl Iterator<Item = &TagFamily> {
+        [
+            &self.sources,
+            &self.resolutions,
+            &self.video_codecs,
+            &self.audio_codecs,
+            &self.languages,
+        ]
+        .into_iter()
+    }
+}
+
+fn families() -> &'static TagFamilies {
+    static FAMILIES: OnceLock<TagFamilies> = OnceLock::new();
+    FAMILIES.get_or_init(|| TagFamilies {
+        sources: TagFamily::new(&SOURCES),
+        resolutions: TagFamily::new(&RESOLUTIONS),
+        video_codecs: TagFamily::new(&VIDEO_CODECS),
+        audio_codecs: TagFamily::new(&AUDIO_CODECS),
+        languages: TagFamily::new(&LANGUAGES),
+    })
+}
+
+fn release_group_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"-(?P<group>[A-Za-z0-9]+)$").unwrap())
+}
+
+// Scene names also use underscores as word separators
+pub fn normalize_separators(stem: &str) -> String {
+    stem.replace('_', ".")
+}
+
+pub fn is_release_tag(token: &str) -> bool {
+    families()
+        .iter()
+        .any(|family| family.first(token).is_some())
+}
+
+// Start of the first release tag, everything before it is the title part
+pub fn release_tags_start(stem: &str) -> Option<usize> {
+    let stem = normalize_separators(stem);
+    families()
+        .iter()
+        .filter_map(|family| family.earliest(&stem))
+        .min()
+}
+
+// Release tags only follow the title, which ends with its episode marker or
+// its year, or else where the first tag starts
+fn release_tags_part(stem: &str) -> &str {
+    let start = episode_marker_end(stem)
+        .or_else(|| find_title_year(stem).map(|year| year.end()))
+        .or_else(|| release_tags_start(stem))
+        .unwrap_or(stem.len());
+
+    &stem[start..]
+}
+
+// `S01E01-E02` and `Movie-2` end with numbers, not with a release group
+fn is_release_group(group: &str) -> bool {
+    let numbered = group
+        .strip_prefix(['E', 'e'])
+        .unwrap_or(group)
+        .bytes()
+        .all(|byte| byte.is_ascii_digit());
+
+    !numbered && !is_release_tag(group)
+}
+
+pub fn parse_release_tags(stem: &str) -> ReleaseTags {
+    let stem = normalize_separators(stem);
+    let tags = release_tags_part(&stem);
+    let families = families();
+
+    // `WEBDL-1080p` ends with a tag, not with the name of a release group
+    let release_group = release_group_regex()
+        .captures(tags)
+        .map(|captures| captures["group"].to_string())
+        .filter(|group| is_release_group(group));
+
+    ReleaseTags {
+        source: families.sources.first(tags),
+        resolution: families.resolutions.first(tags),
+        video_codec: families.video_codecs.first(tags),
+        audio_codec: families.audio_codecs.first(tags),
+        languages: families.languages.all(tags),
+        release_group,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(
+        source: Option<&str>,
+        resolution: Option<&str>,
+        video_codec: Option<&str>,
+        audio_codec: Option<&str>,
+        languages: &[&str],
+        release_group: Option<&str>,
+    ) -> ReleaseTags {
+        ReleaseTags {
+            source: source.map(str::to_string),
+            resolution: resolution.map(str::to_string),
+            video_codec: video_codec.map(str::to_string),
+            audio_codec: audio_codec.map(str::to_string),
+            languages: languages
+                .iter()
+                .map(|language| language.to_string())
+                .collect(),
+            release_group: release_group.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn scene_names() {
+        for (stem, expected) in [
+            (
+                "The.Matrix.1999.1080p.BluRay.x264.DTS-HD.MA.5.1-GRP",
+                tags(
+                    Some("BluRay"),
+                    Some("1080p"),
+                    Some("H.264"),
+                    Some("DTS-HD MA"),
+                    &[],
+                    Some("GRP"),
+                ),
+            ),
+            (
+                "Movie.2019.MULTi.VFF.2160p.WEB-DL.DDP5.1.HEVC-TEAM",
+                tags(
+                    Some("WEB-DL"),
+                    Some("2160p"),
+                    Some("H.265"),
+                    Some("E-AC-3"),
+                    &["MULTi", "VFF"],
+                    Some("TEAM"),
+                ),
+            ),
+            (
+                "Show.S01E01.720p.HDTV.x264-GRP",
+                tags(
+                    Some("HDTV"),
+                    Some("720p"),
+                    Some("H.264"),
+                    None,
+                    &[],
+                    Some("GRP"),
+                ),
+            ),
+            (
+                "Movie_2010_FRENCH_DVDRip_XviD",
+                tags(Some("DVDRip"), None, Some("XviD"), None, &["FRENCH"], None),
+            ),
+            (
+                "Movie (2015) [1080p] [WEBRip] [AAC 5.1]",
+                tags(Some("WEBRip"), Some("1080p"), None, Some("AAC"), &[], None),
+            ),
+        ] {
+            assert_eq!(parse_release_tags(stem), expected, "{}", stem);
+        }
+    }
+
+    #[test]
+    fn title_words_are_not_tags() {
+        for stem in [
+            "The French Connection (1971)",
+            "Charlotte's Web (2006)",
+            "Le VO et le VF (2001)",
+            "Show Vo S01E01",
+            "Spider-Man",
+        ] {
+            assert_eq!(parse_release_tags(stem), ReleaseTags::default(), "{}", stem);
+        }
+
+        assert_eq!(
+            parse_release_tags("The French Connection (1971) 1080p BluRay"),
+            tags(Some("BluRay"), Some("1080p"), None, None, &[], None)
+        );
+    }
+
+    #[test]
+    fn tags_are_whole_tokens() {
+        assert_eq!(
+            parse_release_tags("Movie 2010 VOSTFR").languages,
+            vec!["VOSTFR"]
+        );
+        assert_eq!(parse_release_tags("Movie 2010 VO").languages, vec!["VO"]);
+        assert!(parse_release_tags("Movie 2010 VOyage").languages.is_empty());
+    }
+
+    #[test]
+    fn release_group() {
+        for (stem, group) in [
+            ("Movie.2019.1080p.x264-GRP", Some("GRP")),
+            ("Movie.2019.WEBDL-1080p", None),
+            ("Show S01E01-E02", None),
+            ("Show.S01E01-E02.720p", None),
+            ("Movie.2019.1080p-2", None),
+        ] {
+            assert_eq!(
+                parse_release_tags(stem).release_group.as_deref(),
+                group,
+                "{}",
+                stem
+            );
+        }
+    }
+
+    #[test]
+    fn tags_start() {
+        assert_eq!(release_tags_start("Some.Movie.1080p.WEB-DL"), Some(11));
+        assert_eq!(release_tags_start("Home Movie"), None);
+    }
+}
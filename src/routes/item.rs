@@ -0,0 +1,45 @@
+use crate::models::{ItemDetail, ItemSummary, VersionQuery};
+use crate::routes::media::stream_media_file;
+use crate::services::{find_item, find_version, list_items};
+use crate::state::AppState;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, Method, Response, StatusCode};
+use axum::Json;
+
+pub async fn get_items(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ItemSummary>>, StatusCode> {
+    let items = list_items(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(items))
+}
+
+pub async fn get_item(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ItemDetail>, StatusCode> {
+    let item = find_item(&state.db, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(item))
+}
+
+pub async fn stream_item(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<VersionQuery>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let media = find_version(&state.db, id, query.version)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    stream_media_file(&media, &method, &headers).await
+}
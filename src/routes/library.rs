@@ -0,0 +1,134 @@
+use crate::entities::{library, media};
+use crate::models::{CreateLibrary, LibraryItem, ScanReport};
+use crate::services::{
+    create_library, delete_media, list_libraries, prune_items, prune_tv_catalog, run_library_scan,
+};
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QuerySelect};
+
+pub async fn get_libraries(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<LibraryItem>>, StatusCode> {
+    let libraries = list_libraries(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(libraries.into_iter().map(LibraryItem::from).collect()))
+}
+
+pub async fn post_library(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateLibrary>,
+) -> Result<Json<LibraryItem>, (StatusCode, String)> {
+    if payload.roots.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A library needs at least one root folder".to_string(),
+        ));
+    }
+
+    let library = create_library(
+        &state.db,
+        payload.name,
+        payload.kind,
+        payload.roots,
+        payload.scan_options,
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state.libraries_changed.notify_one();
+
+    Ok(Json(LibraryItem::from(library)))
+}
+
+pub async fn delete_library(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    let library = library::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let media_ids: Vec<i32> = media::Entity::find()
+        .select_only()
+        .column(media::Column::Id)
+        .filter(media::Column::LibraryId.eq(library.id))
+        .into_tuple()
+        .all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    for media_id in media_ids {
+        delete_media(&state.db, media_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    library
+        .delete(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    prune_tv_catalog(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    prune_items(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.libraries_changed.notify_one();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn scan(
+    state: &AppState,
+    libraries: &[library::Model],
+) -> Result<Json<ScanReport>, (StatusCode, String)> {
+    let _guard = state.scan_lock.try_lock().map_err(|_| {
+        (
+            StatusCode::CONFLICT,
+            "The library is already being scanned or updated".to_string(),
+        )
+    })?;
+
+    let report = run_library_scan(&state.db, libraries)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state.trickplay_requested.notify_one();
+
+    Ok(Json(report))
+}
+
+pub async fn scan_library(
+    State(state): State<AppState>,
+) -> Result<Json<ScanReport>, (StatusCode, String)> {
+    let libraries = list_libraries(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    scan(&state, &libraries).await
+}
+
+pub async fn scan_one_library(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ScanReport>, (StatusCode, String)> {
+    let library = library::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Could not find library {}", id),
+        ))?;
+
+    scan(&state, &[library]).await
+}
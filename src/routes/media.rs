@@ -1,28 +1,63 @@
-use crate::models::{CreateMediaItem, MediaInfo, MediaItem};
+use crate::entities::artwork::ArtworkKind;
+use crate::entities::media as media_entity;
+use crate::models::{
+    CreateMediaItem, DuplicateGroup, FrameQuery, ImageQuery, MediaInfoFormat, MediaInfoQuery,
+    MediaItem, MediaPage, MediaQuery, VersionQuery,
+};
 use crate::services::{
-    codec_info, get_content_range, parse_opts, partial_media_content, SubtitleTranscoder,
-    Transcoder, VideoTranscoder, DEFAULT_X264_OPTS,
+    artwork_image, coalesce_ranges, extract_frame, ffprobe_media, file_validators, find_artwork,
+    find_duplicates, find_media, full_media_content, get_content_range, if_range_matches,
+    is_not_modified, list_media, media_content_type, media_info, multipart_boundary,
+    multipart_content_length, multipart_content_type, multipart_media_content, parse_opts,
+    parse_range_header, partial_media_content, set_watched, trickplay_sheet, trickplay_vtt,
+    unsatisfied_content_range, RangeError, SubtitleTranscoder, Transcoder, VideoTranscoder,
+    DEFAULT_X264_OPTS, MAX_RANGES,
 };
+use crate::state::AppState;
 use axum::body::Body;
-use axum::http::{header, HeaderMap, Response, StatusCode};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, Method, Response, StatusCode};
 use axum::Json;
 use ffmpeg_next as ffmpeg;
 use ffmpeg_next::codec::Parameters;
 use ffmpeg_next::format::output;
 use ffmpeg_next::{codec, encoder, format, log, media, packet, Rational};
-use mime_guess::from_path;
+use sea_orm::{ActiveModelTrait, DbErr, Set};
 use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
 use tokio::fs;
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
 
-pub async fn transcode_subtitles(_headers: HeaderMap) -> Result<Response<Body>, StatusCode> {
+// The media file of the requested version, or the sample file used while testing
+async fn transcode_input(
+    state: &AppState,
+    query: &VersionQuery,
+    default_path: &str,
+) -> Result<String, StatusCode> {
+    match query.version {
+        Some(version) => Ok(find_media(&state.db, version).await?.path),
+        None => Ok(default_path.to_string()),
+    }
+}
+
+pub async fn transcode_subtitles(
+    State(state): State<AppState>,
+    Query(query): Query<VersionQuery>,
+    _headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let input_file_path = transcode_input(
+        &state,
+        &query,
+        "./medias/kingsman/Kingsman The Secret Service (2014) WEBDL-1080p.mkv",
+    )
+    .await?;
+
     ffmpeg_next::init().unwrap();
     log::set_level(log::Level::Info);
 
-    let input_file_path = "./medias/kingsman/Kingsman The Secret Service (2014) WEBDL-1080p.mkv";
     let mut input_context = format::input(&input_file_path).unwrap();
-    format::context::input::dump(&input_context, 0, Some(&input_file_path));
+    format::context::input::dump(&input_context, 0, Some(input_file_path.as_str()));
 
     let (subtitle_stream_index, subtitle_params) = input_context
         .streams()
@@ -48,13 +83,23 @@ pub async fn transcode_subtitles(_headers: HeaderMap) -> Result<Response<Body>,
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
 }
 
-pub async fn transcode_media(_headers: HeaderMap) -> Result<Response<Body>, StatusCode> {
+pub async fn transcode_media(
+    State(state): State<AppState>,
+    Query(query): Query<VersionQuery>,
+    _headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let input_file_path = transcode_input(
+        &state,
+        &query,
+        "./medias/Foundation.S02E03.MULTi.1080p.WEBRip.x264.AC3-MULTiViSiON.mkv",
+    )
+    .await?;
+
     ffmpeg::init().unwrap();
     log::set_level(log::Level::Info);
     /* let input_file_name = "Kingsman The Secret Service (2014) WEBDL-1080p.mkv";
     let output_folder = "./medias/kingsman"; */
 
-    let input_file_path = "./medias/Foundation.S02E03.MULTi.1080p.WEBRip.x264.AC3-MULTiViSiON.mkv";
     // let ouput_file_path = output_folder.to_string() + "/" + input_file_name;
 
     let mut ictx = format::input(&input_file_path).unwrap();
@@ -203,94 +248,295 @@ pub async fn transcode_media(_headers: HeaderMap) -> Result<Response<Body>, Stat
     Ok(response)
 }
 
-pub async fn get_media() -> Json<Vec<MediaItem>> {
-    let media = vec![MediaItem {
-        id: 1,
-        title: "Another cool movie".to_string(),
-        path: "/somewhere/movie.mp4".to_string(),
-        created_at: chrono::Utc::now().naive_utc(),
-    }];
+pub async fn get_media(
+    State(state): State<AppState>,
+    Query(query): Query<MediaQuery>,
+) -> Result<Json<MediaPage>, StatusCode> {
+    let page = list_media(&state.db, &query)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Json(media)
+    Ok(Json(page))
 }
 
-pub async fn post_media(Json(payload): Json<CreateMediaItem>) -> Json<MediaItem> {
-    let media = MediaItem {
-        id: 1,
-        title: payload.title,
-        path: payload.path,
-        created_at: chrono::Utc::now().naive_utc(),
-    };
+pub async fn get_duplicates(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<DuplicateGroup>>, StatusCode> {
+    let groups = find_duplicates(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(groups))
+}
+
+pub async fn get_media_by_id(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<MediaItem>, StatusCode> {
+    let media = find_media(&state.db, id).await?;
+
+    Ok(Json(MediaItem::from(media)))
+}
+
+pub async fn mark_watched(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<MediaItem>, StatusCode> {
+    let media = set_watched(&state.db, id, true)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(MediaItem::from(media)))
+}
+
+pub async fn unmark_watched(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<MediaItem>, StatusCode> {
+    let media = set_watched(&state.db, id, false)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(MediaItem::from(media)))
+}
+
+pub async fn post_media(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateMediaItem>,
+) -> Result<Json<MediaItem>, StatusCode> {
+    let media = media_entity::ActiveModel {
+        title: Set(payload.title),
+        path: Set(payload.path),
+        library_id: Set(payload.library_id),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Json(media)
+    Ok(Json(MediaItem::from(media)))
 }
 
 pub async fn get_file(
-    Json(_payload): Json<MediaItem>,
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    method: Method,
+    headers: HeaderMap,
 ) -> Result<Response<Body>, (StatusCode, String)> {
-    let media = MediaItem {
-        id: 1,
-        title: "Enregistrement.mp4".to_string(),
-        path: "./medias/Enregistrement 2024-10-23 165821.mp4".to_string(),
-        created_at: chrono::Utc::now().naive_utc(),
-    };
+    let media = find_media(&state.db, id)
+        .await
+        .map_err(|status| (status, format!("Could not find media {}", id)))?;
+
+    let content_type = media_content_type(&media.path, media.container.as_deref());
 
-    let file_contents = fs::read(&media.path)
+    serve_file(&media.path, &content_type, &method, &headers)
         .await
-        .map_err(|e| (StatusCode::NOT_FOUND, format!("Could not read file: {}", e)))?;
+        .map_err(|status| (status, format!("Could not read file {}", media.path)))
+}
 
-    let mime_type = from_path(&media.path).first_or_octet_stream();
+pub async fn get_media_image(
+    State(state): State<AppState>,
+    Path((id, kind)): Path<(i32, ArtworkKind)>,
+    Query(query): Query<ImageQuery>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let artwork = find_artwork(&state.db, id, kind)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                String::from("Internal server error"),
+            )
+        })?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Could not find this image for media {}", id),
+        ))?;
+
+    let image =
+        tokio::task::spawn_blocking(move || artwork_image(&artwork, query.width, query.height))
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    String::from("Internal server error"),
+                )
+            })?
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => (
+                    StatusCode::NOT_FOUND,
+                    format!("Could not read image: {}", e),
+                ),
+                _ => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Could not render image: {}", e),
+                ),
+            })?;
 
     let response = Response::builder()
-        .header(header::CONTENT_TYPE, mime_type.as_ref())
-        .header(header::CONTENT_LENGTH, file_contents.len().to_string())
-        .body(Body::from(file_contents))
+        .header(header::CONTENT_TYPE, image.mime_type)
+        .header(header::CONTENT_LENGTH, image.bytes.len().to_string())
+        .body(Body::from(image.bytes))
         .unwrap();
 
     Ok(response)
 }
 
-pub async fn get_media_info() -> Result<Response<Body>, (StatusCode, String)> {
-    let media = MediaItem {
-        id: 1,
-        title: "Nightcrawler".to_string(),
-        path: "./medias/Kingsman The Secret Service (2014) WEBDL-1080p.mkv".to_string(),
-        created_at: chrono::Utc::now().naive_utc(),
-    };
-
-    if let Err(_e) = ffmpeg::init() {
+pub async fn get_media_frame(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<FrameQuery>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    if !query.t.is_finite() || query.t < 0.0 {
         return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "ffmpeg init failed".to_string(),
+            StatusCode::BAD_REQUEST,
+            format!("Invalid timestamp {}", query.t),
         ));
     }
 
-    let ictx = match ffmpeg::format::input(&media.path) {
-        Ok(ictx) => ictx,
-        Err(_e) => {
+    let media = find_media(&state.db, id)
+        .await
+        .map_err(|status| (status, format!("Could not find media {}", id)))?;
+
+    let path = PathBuf::from(&media.path);
+    let image = tokio::task::spawn_blocking(move || {
+        extract_frame(&path, query.t, query.width, query.format)
+    })
+    .await
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            String::from("Internal server error"),
+        )
+    })?
+    .map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => (
+            StatusCode::NOT_FOUND,
+            format!("Could not read frame: {}", e),
+        ),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Could not extract frame: {}", e),
+        ),
+    })?;
+
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, image.mime_type)
+        .header(header::CONTENT_LENGTH, image.bytes.len().to_string())
+        .body(Body::from(image.bytes))
+        .unwrap();
+
+    Ok(response)
+}
+
+pub async fn get_trickplay_vtt(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    find_media(&state.db, id)
+        .await
+        .map_err(|status| (status, format!("Could not find media {}", id)))?;
+
+    let vtt = match fs::read(trickplay_vtt(id)).await {
+        Ok(vtt) => vtt,
+        Err(_) => {
+            state.trickplay_requested.notify_one();
+
             return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                String::from("Internal server error"),
-            ))
+                StatusCode::NOT_FOUND,
+                format!("Previews of media {} are not generated yet", id),
+            ));
         }
     };
 
-    format::context::input::dump(&ictx, 0, Some(&media.path));
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/vtt; charset=utf-8")
+        .header(header::CONTENT_LENGTH, vtt.len().to_string())
+        .body(Body::from(vtt))
+        .unwrap();
 
-    let mut codecs = Vec::new();
+    Ok(response)
+}
 
-    // Access each streams individually with their information
-    for (_index, input_stream) in ictx.streams().enumerate() {
-        let codec_info = codec_info(input_stream);
-        codecs.push(codec_info);
-    }
+// Sprite sheets referenced by the trickplay track, `/medias/3/trickplay/0.jpg`
+pub async fn get_trickplay_sheet(
+    Path((id, sheet)): Path<(i32, String)>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let sheet = sheet
+        .strip_suffix(".jpg")
+        .and_then(|sheet| sheet.parse::<u32>().ok())
+        .ok_or((StatusCode::NOT_FOUND, format!("Unknown sheet {}", sheet)))?;
+
+    let image = fs::read(trickplay_sheet(id, sheet)).await.map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Could not read sheet: {}", e),
+        )
+    })?;
 
-    let info = MediaInfo {
-        name: media.title,
-        codecs,
-    };
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "image/jpeg")
+        .header(header::CONTENT_LENGTH, image.len().to_string())
+        .body(Body::from(image))
+        .unwrap();
+
+    Ok(response)
+}
+
+pub async fn get_media_info(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<MediaInfoQuery>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let media = find_media(&state.db, id)
+        .await
+        .map_err(|status| (status, format!("Could not find media {}", id)))?;
+
+    let response_body = match query.format {
+        MediaInfoFormat::Json => {
+            let info = media_info(&state.db, media).await.map_err(|e| match e {
+                DbErr::RecordNotFound(_) => (
+                    StatusCode::NOT_FOUND,
+                    format!("Could not find media {}", id),
+                ),
+                e => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Could not probe media: {}", e),
+                ),
+            })?;
+
+            serde_json::to_string(&info).unwrap()
+        }
+        // Probed live, ffprobe reports raw fields that are not stored at scan time
+        MediaInfoFormat::Ffprobe => {
+            if !fs::try_exists(&media.path).await.unwrap_or(false) {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    format!("Could not find media {}", id),
+                ));
+            }
 
-    let response_body = serde_json::to_string(&info).unwrap();
+            let path = PathBuf::from(&media.path);
+            let output = tokio::task::spawn_blocking(move || ffprobe_media(&path))
+                .await
+                .map_err(|_| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        String::from("Internal server error"),
+                    )
+                })?
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Could not probe media: {}", e),
+                    )
+                })?;
+
+            serde_json::to_string(&output).unwrap()
+        }
+    };
 
     let response = Response::builder()
         .header(header::CONTENT_TYPE, "application/json")
@@ -300,57 +546,124 @@ pub async fn get_media_info() -> Result<Response<Body>, (StatusCode, String)> {
     Ok(response)
 }
 
-pub async fn stream_media(headers: HeaderMap) -> Result<Response<Body>, StatusCode> {
-    let media = MediaItem {
-        id: 1,
-        title: "Enregistrement.mp4".to_string(),
-        path: "./medias/Enregistrement 2024-10-23 165821.mp4".to_string(),
-        created_at: chrono::Utc::now().naive_utc(),
-    };
+pub async fn stream_media(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let media = find_media(&state.db, id).await?;
 
-    let mut file = File::open(&media.path)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
-
-    let metadata = file.metadata().await.map_err(|_| StatusCode::NOT_FOUND)?;
-    let file_size = metadata.len();
+    stream_media_file(&media, &method, &headers).await
+}
 
-    // If the request contains Content Range, serve a ranged stream
-    if let Some(range_header) = headers.get(header::RANGE) {
-        let (body, start, end, chunk_size) =
-            partial_media_content(&mut file, range_header, file_size).await?;
+pub async fn stream_media_file(
+    media: &media_entity::Model,
+    method: &Method,
+    headers: &HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let content_type = media_content_type(&media.path, media.container.as_deref());
 
-        dbg!(start, end, file_size);
+    serve_file(&media.path, &content_type, method, headers).await
+}
 
-        let response = Response::builder()
-            .status(StatusCode::PARTIAL_CONTENT)
-            .header(header::CONTENT_TYPE, "video/mp4")
-            .header(
-                header::CONTENT_RANGE,
-                get_content_range(start, end, file_size),
-            )
-            .header(header::CONTENT_LENGTH, chunk_size.to_string())
-            .body(body)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+// Serves a file with its validators, answering conditional and range
+// requests. HEAD requests get the same headers without reading the file, so
+// players can learn its size and type beforehand.
+async fn serve_file(
+    path: &str,
+    content_type: &str,
+    method: &Method,
+    headers: &HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let file = File::open(path).await.map_err(|_| StatusCode::NOT_FOUND)?;
 
-        dbg!(&response);
+    let metadata = file.metadata().await.map_err(|_| StatusCode::NOT_FOUND)?;
+    let file_size = metadata.len();
+    let validators = file_validators(&metadata);
+    let head = method == Method::HEAD;
+
+    let response = |status: StatusCode| {
+        let builder = Response::builder()
+            .status(status)
+            .header(header::ETAG, &validators.etag)
+            .header(header::ACCEPT_RANGES, "bytes");
+
+        match validators.last_modified_header() {
+            Some(last_modified) => builder.header(header::LAST_MODIFIED, last_modified),
+            None => builder,
+        }
+    };
 
-        return Ok(response);
+    if is_not_modified(headers, &validators) {
+        return response(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
     }
 
-    let mut buffer = Vec::new();
+    // A stale `If-Range` turns the request into a plain GET of the new file
+    let range = headers
+        .get(header::RANGE)
+        .filter(|_| if_range_matches(headers, &validators))
+        .and_then(|range| range.to_str().ok())
+        .map(|range| parse_range_header(range, file_size));
+
+    match range.map(|ranges| ranges.map(coalesce_ranges)) {
+        // A single range is served as a ranged stream
+        Some(Ok(ranges)) if ranges.len() == 1 => {
+            let range = ranges[0];
+            let body = if head {
+                Body::empty()
+            } else {
+                partial_media_content(file, range).await?
+            };
+
+            return response(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_TYPE, content_type)
+                .header(
+                    header::CONTENT_RANGE,
+                    get_content_range(range.start, range.end, file_size),
+                )
+                .header(header::CONTENT_LENGTH, range.length().to_string())
+                .body(body)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Some(Ok(ranges)) if ranges.len() <= MAX_RANGES => {
+            let boundary = multipart_boundary();
+            let content_length =
+                multipart_content_length(&ranges, &boundary, content_type, file_size);
+            let body = if head {
+                Body::empty()
+            } else {
+                multipart_media_content(file, ranges, &boundary, content_type, file_size).await
+            };
+
+            return response(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_TYPE, multipart_content_type(&boundary))
+                .header(header::CONTENT_LENGTH, content_length.to_string())
+                .body(body)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Some(Err(RangeError::Unsatisfiable)) => {
+            return response(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, unsatisfied_content_range(file_size))
+                .body(Body::empty())
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        // Malformed headers and abusive range sets are ignored, the whole
+        // file is sent
+        _ => {}
+    }
 
-    file.read_to_end(&mut buffer)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let body = if head {
+        Body::empty()
+    } else {
+        full_media_content(file)
+    };
 
-    let body = Body::from(buffer);
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "video/mp4")
-        .header("Content-Length", file_size.to_string())
+    response(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, file_size.to_string())
         .body(body)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(response)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
 }
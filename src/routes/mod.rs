@@ -1,2 +1,6 @@
+pub mod item;
+pub mod library;
 pub mod media;
+pub mod search;
+pub mod series;
 
@@ -0,0 +1,27 @@
+use crate::models::{MediaItem, SearchQuery, SearchResult};
+use crate::services::{search_media, DEFAULT_SEARCH_LIMIT};
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+
+pub async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, 100);
+
+    let results = search_media(&state.db, &query.q, limit)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        results
+            .into_iter()
+            .map(|(score, media)| SearchResult {
+                score,
+                media: MediaItem::from(media),
+            })
+            .collect(),
+    ))
+}
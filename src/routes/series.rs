@@ -0,0 +1,40 @@
+use crate::models::{EpisodeItem, SeasonItem, SeriesItem};
+use crate::services::{list_episodes, list_seasons, list_series};
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+
+pub async fn get_series(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SeriesItem>>, StatusCode> {
+    let series = list_series(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(series))
+}
+
+pub async fn get_seasons(
+    State(state): State<AppState>,
+    Path(series_id): Path<i32>,
+) -> Result<Json<Vec<SeasonItem>>, StatusCode> {
+    let seasons = list_seasons(&state.db, series_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(seasons))
+}
+
+pub async fn get_episodes(
+    State(state): State<AppState>,
+    Path((series_id, season_number)): Path<(i32, i32)>,
+) -> Result<Json<Vec<EpisodeItem>>, StatusCode> {
+    let episodes = list_episodes(&state.db, series_id, season_number)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(episodes))
+}
@@ -0,0 +1,349 @@
+use crate::entities::artwork::{self, ArtworkKind};
+use crate::entities::media;
+use crate::parsers::is_season_folder;
+use crate::services::Attachment;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::UNIX_EPOCH;
+use std::{env, fs, io};
+
+const IMAGE_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+pub struct ArtworkImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+}
+
+// Generated images are kept under `CACHE_DIR`, defaults to the local `./cache` folder
+pub fn cache_dir() -> PathBuf {
+    env::var("CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./cache"))
+}
+
+// First `<name>.<extension>` image of `dir`, names in order of preference
+fn find_image(dir: &Path, names: &[&str]) -> Option<PathBuf> {
+    names
+        .iter()
+        .flat_map(|name| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .map(move |extension| dir.join(format!("{}.{}", name, extension)))
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+// Kodi naming: `<name>-thumb.jpg` next to the file, `poster.jpg`, `fanart.jpg`
+// and `banner.png` in the movie or show folder, season posters as
+// `season02-poster.jpg` in the show folder or `poster.jpg` in the season folder.
+fn local_artwork(media: &media::Model, path: &Path) -> Vec<(ArtworkKind, PathBuf)> {
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem()) else {
+        return Vec::new();
+    };
+    let stem = stem.to_string_lossy();
+    let named = |suffix: &str| format!("{}-{}", stem, suffix);
+
+    // Episodes in a season folder find the show artwork one level up
+    let in_season_folder = media.season_number.is_some()
+        && dir
+            .file_name()
+            .is_some_and(|name| is_season_folder(&name.to_string_lossy()));
+    let title_dir = match dir.parent() {
+        Some(parent) if in_season_folder => parent,
+        _ => dir,
+    };
+
+    let poster = find_image(dir, &[&named("poster")])
+        .or_else(|| find_image(title_dir, &["poster", "folder"]));
+    let fanart = find_image(dir, &[&named("fanart")])
+        .or_else(|| find_image(title_dir, &["fanart", "backdrop"]));
+    let banner =
+        find_image(dir, &[&named("banner")]).or_else(|| find_image(title_dir, &["banner"]));
+    let thumb = find_image(dir, &[&named("thumb")]);
+    let season_poster = media.season_number.and_then(|season| {
+        let name = match season {
+            0 => "season-specials-poster".to_string(),
+            season => format!("season{:02}-poster", season),
+        };
+
+        find_image(title_dir, &[&name]).or_else(|| {
+            in_season_folder
+                .then(|| find_image(dir, &["poster", "folder"]))
+                .flatten()
+        })
+    });
+
+    [
+        (ArtworkKind::Poster, poster),
+        (ArtworkKind::Fanart, fanart),
+        (ArtworkKind::Banner, banner),
+        (ArtworkKind::Thumb, thumb),
+        (ArtworkKind::SeasonPoster, season_poster),
+    ]
+    .into_iter()
+    .filter_map(|(kind, image)| Some((kind, image?)))
+    .collect()
+}
+
+// Matroska cover art naming: `cover.jpg` and the landscape `cover_land.jpg`,
+// each with an optional `small_` variant listed after the full size one.
+fn embedded_artwork(attachments: &[Attachment]) -> Vec<(ArtworkKind, i32)> {
+    let mut covers: Vec<(bool, ArtworkKind, i32)> = attachments
+        .iter()
+        .filter_map(|attachment| {
+            let file_name = Path::new(&attachment.file_name);
+            let extension = file_name.extension()?.to_str()?.to_lowercase();
+
+            if !attachment.mime_type.starts_with("image/")
+                && !IMAGE_EXTENSIONS.contains(&extension.as_str())
+            {
+                return None;
+            }
+
+            let stem = file_name.file_stem()?.to_str()?.to_lowercase();
+            let (small, name) = match stem.strip_prefix("small_") {
+                Some(name) => (true, name),
+                None => (false, stem.as_str()),
+            };
+            let kind = match name {
+                "cover" => ArtworkKind::Poster,
+                "cover_land" => ArtworkKind::Fanart,
+                _ => return None,
+            };
+
+            Some((small, kind, attachment.index))
+        })
+        .collect();
+
+    covers.sort_by_key(|(small, _, _)| *small);
+    covers
+        .into_iter()
+        .map(|(_, kind, index)| (kind, index))
+        .collect()
+}
+
+// Refreshes the artwork of a media file. Images next to it are looked up on
+// every scan, embedded ones only come with a probe and are kept otherwise.
+// Generated thumbnails are left to `ensure_thumbnail`.
+pub async fn sync_artwork(
+    db: &DatabaseConnection,
+    media: &media::Model,
+    attachments: Option<&[Attachment]>,
+) -> Result<(), DbErr> {
+    let current: Vec<(ArtworkKind, String, Option<i32>)> = artwork::Entity::find()
+        .filter(artwork::Column::MediaId.eq(media.id))
+        .filter(artwork::Column::IsGenerated.eq(false))
+        .order_by_asc(artwork::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|artwork| (artwork.kind, artwork.path, artwork.stream_index))
+        .collect();
+
+    let embedded: Vec<(ArtworkKind, i32)> = match attachments {
+        Some(attachments) => embedded_artwork(attachments),
+        None => current
+            .iter()
+            .filter_map(|(kind, _, index)| Some((*kind, (*index)?)))
+            .collect(),
+    };
+
+    let wanted: Vec<(ArtworkKind, String, Option<i32>)> =
+        local_artwork(media, Path::new(&media.path))
+            .into_iter()
+            .map(|(kind, image)| (kind, image.to_string_lossy().to_string(), None))
+            .chain(
+                embedded
+                    .into_iter()
+                    .map(|(kind, index)| (kind, media.path.clone(), Some(index))),
+            )
+            .collect();
+
+    if wanted == current {
+        return Ok(());
+    }
+
+    artwork::Entity::delete_many()
+        .filter(artwork::Column::MediaId.eq(media.id))
+        .filter(artwork::Column::IsGenerated.eq(false))
+        .exec(db)
+        .await?;
+
+    if wanted.is_empty() {
+        return Ok(());
+    }
+
+    artwork::Entity::insert_many(wanted.into_iter().map(|(kind, path, stream_index)| {
+        artwork::ActiveModel {
+            media_id: Set(media.id),
+            kind: Set(kind),
+            path: Set(path),
+            stream_index: Set(stream_index),
+            is_generated: Set(false),
+            ..Default::default()
+        }
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+// Images next to the media file take precedence over embedded ones, which
+// take precedence over generated thumbnails
+pub async fn find_artwork(
+    db: &DatabaseConnection,
+    media_id: i32,
+    kind: ArtworkKind,
+) -> Result<Option<artwork::Model>, DbErr> {
+    Ok(artwork::Entity::find()
+        .filter(artwork::Column::MediaId.eq(media_id))
+        .filter(artwork::Column::Kind.eq(kind))
+        .order_by_asc(artwork::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .min_by_key(|artwork| (artwork.is_generated, artwork.stream_index.is_some())))
+}
+
+// Attachments carry their whole content in the codec extradata
+fn read_attachment(path: &Path, index: i32) -> Result<Vec<u8>, ffmpeg_next::Error> {
+    ffmpeg_next::init()?;
+
+    let ictx = ffmpeg_next::format::input(&path)?;
+    let stream = ictx
+        .stream(index as usize)
+        .ok_or(ffmpeg_next::Error::StreamNotFound)?;
+    let parameters = stream.parameters();
+    let raw = unsafe { &*parameters.as_ptr() };
+
+    if raw.extradata.is_null() || raw.extradata_size <= 0 {
+        return Err(ffmpeg_next::Error::InvalidData);
+    }
+
+    Ok(unsafe { std::slice::from_raw_parts(raw.extradata, raw.extradata_size as usize) }.to_vec())
+}
+
+fn read_artwork(artwork: &artwork::Model) -> io::Result<Vec<u8>> {
+    match artwork.stream_index {
+        Some(index) => read_attachment(Path::new(&artwork.path), index).map_err(io::Error::other),
+        None => fs::read(&artwork.path),
+    }
+}
+
+// Keyed by the source and its size and mtime, so replaced images are rendered again
+fn cache_key(
+    artwork: &artwork::Model,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> io::Result<String> {
+    let metadata = fs::metadata(&artwork.path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|modified| modified.as_secs())
+        .unwrap_or_default();
+
+    let source = format!(
+        "{}:{:?}:{}:{}:{:?}x{:?}",
+        artwork.path,
+        artwork.stream_index,
+        metadata.len(),
+        modified,
+        width,
+        height
+    );
+
+    Ok(format!("{:x}", Sha256::digest(source)))
+}
+
+// Written aside then renamed so that concurrent requests never read a partial file
+pub fn write_cache(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    static PARTIAL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let partial = path.with_extension(format!(
+        "{}.part",
+        PARTIAL_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, path)
+}
+
+fn image_format(bytes: &[u8]) -> io::Result<ImageFormat> {
+    image::guess_format(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// The artwork scaled down to fit within `width` x `height`, never enlarged.
+// PNG images stay PNG to keep their transparency, anything else becomes JPEG.
+pub fn artwork_image(
+    artwork: &artwork::Model,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> io::Result<ArtworkImage> {
+    let width = width.filter(|width| *width > 0);
+    let height = height.filter(|height| *height > 0);
+
+    if width.is_none() && height.is_none() {
+        let bytes = read_artwork(artwork)?;
+
+        return Ok(ArtworkImage {
+            mime_type: image_format(&bytes)?.to_mime_type(),
+            bytes,
+        });
+    }
+
+    let cached = cache_dir()
+        .join("images")
+        .join(cache_key(artwork, width, height)?);
+
+    for format in [ImageFormat::Jpeg, ImageFormat::Png] {
+        if let Ok(bytes) = fs::read(cached.with_extension(format.extensions_str()[0])) {
+            return Ok(ArtworkImage {
+                bytes,
+                mime_type: format.to_mime_type(),
+            });
+        }
+    }
+
+    let source = read_artwork(artwork)?;
+    let source_format = image_format(&source)?;
+    let decoded = image::load_from_memory_with_format(&source, source_format)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let resized = decoded.resize(
+        width.unwrap_or(u32::MAX).min(decoded.width()),
+        height.unwrap_or(u32::MAX).min(decoded.height()),
+        FilterType::Lanczos3,
+    );
+    let (format, resized) = match source_format {
+        ImageFormat::Png => (ImageFormat::Png, resized),
+        _ => (
+            ImageFormat::Jpeg,
+            DynamicImage::ImageRgb8(resized.to_rgb8()),
+        ),
+    };
+
+    let mut bytes = Vec::new();
+    resized
+        .write_to(&mut Cursor::new(&mut bytes), format)
+        .map_err(io::Error::other)?;
+
+    // A failing cache only costs a resize on the next request
+    if let Err(e) = write_cache(&cached.with_extension(format.extensions_str()[0]), &bytes) {
+        eprintln!("could not cache {}: {}", cached.display(), e);
+    }
+
+    Ok(ArtworkImage {
+        bytes,
+        mime_type: format.to_mime_type(),
+    })
+}
@@ -0,0 +1,270 @@
+use crate::entities::{episode, media, season, series};
+use crate::models::{season_name, EpisodeItem, MediaItem, SeasonItem, SeriesItem};
+use sea_orm::sea_query::{Expr, Func, Query};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    JoinType, ModelTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+};
+use std::collections::HashMap;
+
+#[derive(FromQueryResult)]
+struct GroupCount {
+    group_id: i32,
+    count: i64,
+}
+
+fn into_count_map(counts: Vec<GroupCount>) -> HashMap<i32, i64> {
+    counts
+        .into_iter()
+        .map(|count| (count.group_id, count.count))
+        .collect()
+}
+
+// `The Office` and `The office` are the same series, the first spelling seen names it
+async fn find_or_create_series(
+    db: &DatabaseConnection,
+    name: &str,
+) -> Result<series::Model, DbErr> {
+    if let Some(series) = series::Entity::find()
+        .filter(Expr::expr(Func::lower(Expr::col(series::Column::Name))).eq(name.to_lowercase()))
+        .one(db)
+        .await?
+    {
+        return Ok(series);
+    }
+
+    series::ActiveModel {
+        name: Set(name.to_string()),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+async fn find_or_create_season(
+    db: &DatabaseConnection,
+    series_id: i32,
+    season_number: i32,
+) -> Result<season::Model, DbErr> {
+    if let Some(season) = season::Entity::find()
+        .filter(season::Column::SeriesId.eq(series_id))
+        .filter(season::Column::SeasonNumber.eq(season_number))
+        .one(db)
+        .await?
+    {
+        return Ok(season);
+    }
+
+    season::ActiveModel {
+        series_id: Set(series_id),
+        season_number: Set(season_number),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+// Keeps the episode row of a media file in line with its parsed series, season and episode
+pub async fn sync_episode(db: &DatabaseConnection, media: &media::Model) -> Result<(), DbErr> {
+    let existing = episode::Entity::find()
+        .filter(episode::Column::MediaId.eq(media.id))
+        .one(db)
+        .await?;
+
+    let (Some(series_name), Some(season_number), Some(episode_number)) = (
+        &media.series_name,
+        media.season_number,
+        media.episode_number,
+    ) else {
+        if let Some(existing) = existing {
+            existing.delete(db).await?;
+        }
+        return Ok(());
+    };
+
+    let series = find_or_create_series(db, series_name).await?;
+    let season = find_or_create_season(db, series.id, season_number).await?;
+
+    match existing {
+        Some(existing) => {
+            let mut active: episode::ActiveModel = existing.into();
+            active.season_id.set_if_not_equals(season.id);
+            active.episode_number.set_if_not_equals(episode_number);
+            active
+                .episode_number_end
+                .set_if_not_equals(media.episode_number_end);
+
+            if active.is_changed() {
+                active.update(db).await?;
+            }
+        }
+        None => {
+            episode::ActiveModel {
+                season_id: Set(season.id),
+                media_id: Set(media.id),
+                episode_number: Set(episode_number),
+                episode_number_end: Set(media.episode_number_end),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Drops seasons left without episodes, then series left without seasons
+pub async fn prune_tv_catalog(db: &DatabaseConnection) -> Result<(), DbErr> {
+    season::Entity::delete_many()
+        .filter(
+            season::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(episode::Column::SeasonId)
+                    .from(episode::Entity)
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await?;
+
+    series::Entity::delete_many()
+        .filter(
+            series::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(season::Column::SeriesId)
+                    .from(season::Entity)
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn list_series(db: &DatabaseConnection) -> Result<Vec<SeriesItem>, DbErr> {
+    let season_counts = season::Entity::find()
+        .select_only()
+        .column_as(season::Column::SeriesId, "group_id")
+        .column_as(season::Column::Id.count(), "count")
+        .group_by(season::Column::SeriesId)
+        .into_model::<GroupCount>()
+        .all(db)
+        .await?;
+
+    let episode_counts = episode::Entity::find()
+        .select_only()
+        .column_as(season::Column::SeriesId, "group_id")
+        .column_as(episode::Column::Id.count(), "count")
+        .join(JoinType::InnerJoin, episode::Relation::Season.def())
+        .group_by(season::Column::SeriesId)
+        .into_model::<GroupCount>()
+        .all(db)
+        .await?;
+
+    let season_counts = into_count_map(season_counts);
+    let episode_counts = into_count_map(episode_counts);
+
+    let series = series::Entity::find()
+        .order_by_asc(series::Column::Name)
+        .all(db)
+        .await?;
+
+    Ok(series
+        .into_iter()
+        .map(|series| SeriesItem {
+            season_count: season_counts.get(&series.id).copied().unwrap_or(0),
+            episode_count: episode_counts.get(&series.id).copied().unwrap_or(0),
+            id: series.id,
+            name: series.name,
+        })
+        .collect())
+}
+
+pub async fn list_seasons(
+    db: &DatabaseConnection,
+    series_id: i32,
+) -> Result<Option<Vec<SeasonItem>>, DbErr> {
+    if series::Entity::find_by_id(series_id)
+        .one(db)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+
+    let seasons = season::Entity::find()
+        .filter(season::Column::SeriesId.eq(series_id))
+        .order_by_asc(season::Column::SeasonNumber)
+        .all(db)
+        .await?;
+
+    let episode_counts = episode::Entity::find()
+        .select_only()
+        .column_as(episode::Column::SeasonId, "group_id")
+        .column_as(episode::Column::Id.count(), "count")
+        .filter(episode::Column::SeasonId.is_in(seasons.iter().map(|season| season.id)))
+        .group_by(episode::Column::SeasonId)
+        .into_model::<GroupCount>()
+        .all(db)
+        .await?;
+    let episode_counts = into_count_map(episode_counts);
+
+    Ok(Some(
+        seasons
+            .into_iter()
+            .map(|season| SeasonItem {
+                episode_count: episode_counts.get(&season.id).copied().unwrap_or(0),
+                name: season_name(season.season_number),
+                id: season.id,
+                series_id: season.series_id,
+                season_number: season.season_number,
+            })
+            .collect(),
+    ))
+}
+
+pub async fn list_episodes(
+    db: &DatabaseConnection,
+    series_id: i32,
+    season_number: i32,
+) -> Result<Option<Vec<EpisodeItem>>, DbErr> {
+    let Some(season) = season::Entity::find()
+        .filter(season::Column::SeriesId.eq(series_id))
+        .filter(season::Column::SeasonNumber.eq(season_number))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let mut episodes = episode::Entity::find()
+        .filter(episode::Column::SeasonId.eq(season.id))
+        .find_also_related(media::Entity)
+        .filter(media::Column::Missing.eq(false))
+        .order_by_asc(episode::Column::EpisodeNumber)
+        .order_by_asc(media::Column::Id)
+        .all(db)
+        .await?;
+
+    // Other versions of an episode, such as a 720p copy, are listed once under
+    // the first one indexed
+    episodes.dedup_by_key(|(episode, _)| episode.episode_number);
+
+    Ok(Some(
+        episodes
+            .into_iter()
+            .filter_map(|(episode, media)| {
+                Some(EpisodeItem {
+                    id: episode.id,
+                    season_number: season.season_number,
+                    episode_number: episode.episode_number,
+                    episode_number_end: episode.episode_number_end,
+                    media: MediaItem::from(media?),
+                })
+            })
+            .collect(),
+    ))
+}
@@ -0,0 +1,90 @@
+use crate::entities::media;
+use crate::models::{DuplicateFile, DuplicateGroup};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Bytes hashed at the start, the middle and the end of a file
+const SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// Content fingerprint made of the file size and three sampled blocks, so that
+/// copies of multi-gigabyte files are recognised without reading them fully.
+pub fn fingerprint_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    if size <= SAMPLE_SIZE * 3 {
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        hasher.update(&content);
+    } else {
+        let mut block = vec![0; SAMPLE_SIZE as usize];
+
+        for offset in [0, size / 2 - SAMPLE_SIZE / 2, size - SAMPLE_SIZE] {
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut block)?;
+            hasher.update(&block);
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Present media sharing a fingerprint, largest files first
+pub async fn find_duplicates(db: &DatabaseConnection) -> Result<Vec<DuplicateGroup>, DbErr> {
+    let fingerprints: Vec<String> = media::Entity::find()
+        .select_only()
+        .column(media::Column::Fingerprint)
+        .filter(media::Column::Fingerprint.is_not_null())
+        .filter(media::Column::Missing.eq(false))
+        .group_by(media::Column::Fingerprint)
+        .having(Expr::expr(media::Column::Id.count()).gt(1))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let rows = media::Entity::find()
+        .filter(media::Column::Fingerprint.is_in(fingerprints))
+        .filter(media::Column::Missing.eq(false))
+        .order_by_asc(media::Column::Fingerprint)
+        .order_by_asc(media::Column::Path)
+        .all(db)
+        .await?;
+
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+
+    for row in rows {
+        let Some(fingerprint) = row.fingerprint.clone() else {
+            continue;
+        };
+
+        let file = DuplicateFile {
+            id: row.id,
+            title: row.title,
+            path: row.path,
+            size: row.size,
+            library_id: row.library_id,
+        };
+
+        match groups.last_mut() {
+            Some(group) if group.fingerprint == fingerprint => group.files.push(file),
+            _ => groups.push(DuplicateGroup {
+                fingerprint,
+                size: file.size,
+                files: vec![file],
+            }),
+        }
+    }
+
+    groups.sort_by(|a, b| b.size.cmp(&a.size));
+
+    Ok(groups)
+}
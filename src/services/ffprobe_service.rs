@@ -0,0 +1,181 @@
+use crate::models::{FfprobeDisposition, FfprobeFormat, FfprobeOutput, FfprobeStream};
+use crate::services::c_string;
+use ffmpeg_next::color::{Primaries, Range, Space, TransferCharacteristic};
+use ffmpeg_next::format::{Pixel, Sample};
+use ffmpeg_next::{codec, ffi, DictionaryRef, Rational, Stream};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+fn ratio(value: Rational, separator: char) -> String {
+    format!("{}{}{}", value.numerator(), separator, value.denominator())
+}
+
+// ffprobe prints times with microsecond precision
+fn seconds(value: f64) -> String {
+    format!("{:.6}", value)
+}
+
+fn timestamp(value: i64, time_base: Rational) -> Option<String> {
+    (value != ffi::AV_NOPTS_VALUE).then(|| seconds(value as f64 * f64::from(time_base)))
+}
+
+fn positive<T: Default + PartialOrd + ToString>(value: T) -> Option<String> {
+    (value > T::default()).then(|| value.to_string())
+}
+
+// Same rendering as `av_fourcc_make_string`: printable bytes as-is, others as `[N]`
+fn fourcc(tag: u32) -> String {
+    tag.to_le_bytes()
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_alphanumeric() || b". -_".contains(&byte) {
+                (byte as char).to_string()
+            } else {
+                format!("[{}]", byte)
+            }
+        })
+        .collect()
+}
+
+fn tags(metadata: DictionaryRef) -> BTreeMap<String, String> {
+    metadata
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn ffprobe_stream(stream: Stream) -> FfprobeStream {
+    let parameters = stream.parameters();
+    let medium = parameters.medium();
+    let codec_id = parameters.id();
+    let time_base = stream.time_base();
+
+    let (codec_tag, bits_per_sample, disposition, nb_frames) = unsafe {
+        let raw_parameters = parameters.as_ptr();
+        let raw_stream = stream.as_ptr();
+        (
+            (*raw_parameters).codec_tag,
+            (*raw_parameters).bits_per_coded_sample,
+            (*raw_stream).disposition,
+            (*raw_stream).nb_frames,
+        )
+    };
+
+    let context = codec::context::Context::from_parameters(parameters.clone()).ok();
+    let raw = context
+        .as_ref()
+        .map(|context| unsafe { &*context.as_ptr() });
+    let video = raw.filter(|_| medium == ffmpeg_next::media::Type::Video);
+    let audio = raw.filter(|_| medium == ffmpeg_next::media::Type::Audio);
+
+    let mut layout: [std::ffi::c_char; 64] = [0; 64];
+    let channel_layout = audio.and_then(|audio| {
+        let written = unsafe {
+            ffi::av_channel_layout_describe(&audio.ch_layout, layout.as_mut_ptr(), layout.len())
+        };
+        (written > 0).then(|| c_string(layout.as_ptr())).flatten()
+    });
+
+    let start_pts = stream.start_time();
+    let duration_ts = stream.duration();
+
+    FfprobeStream {
+        index: stream.index() as i32,
+        codec_name: codec_id.name().to_string(),
+        codec_long_name: unsafe {
+            let descriptor = ffi::avcodec_descriptor_get(codec_id.into());
+            descriptor
+                .as_ref()
+                .and_then(|descriptor| c_string(descriptor.long_name))
+        },
+        profile: raw.and_then(|raw| {
+            c_string(unsafe { ffi::avcodec_profile_name(codec_id.into(), raw.profile) })
+        }),
+        codec_type: c_string(unsafe { ffi::av_get_media_type_string(medium.into()) }),
+        codec_tag_string: fourcc(codec_tag),
+        codec_tag: format!("0x{:04x}", codec_tag),
+        width: video.map(|video| video.width),
+        height: video.map(|video| video.height),
+        sample_aspect_ratio: video
+            .map(|video| Rational::from(video.sample_aspect_ratio))
+            .filter(|aspect| aspect.numerator() > 0)
+            .map(|aspect| ratio(aspect, ':')),
+        pix_fmt: video.and_then(|video| {
+            Pixel::from(video.pix_fmt)
+                .descriptor()
+                .map(|descriptor| descriptor.name().to_string())
+        }),
+        level: video.map(|video| video.level).filter(|level| *level > 0),
+        color_range: video
+            .and_then(|video| Range::from(video.color_range).name().map(str::to_string)),
+        color_space: video
+            .and_then(|video| Space::from(video.colorspace).name().map(str::to_string)),
+        color_transfer: video.and_then(|video| {
+            TransferCharacteristic::from(video.color_trc)
+                .name()
+                .map(str::to_string)
+        }),
+        color_primaries: video.and_then(|video| {
+            Primaries::from(video.color_primaries)
+                .name()
+                .map(str::to_string)
+        }),
+        sample_fmt: audio
+            .map(|audio| Sample::from(audio.sample_fmt))
+            .filter(|sample| *sample != Sample::None)
+            .map(|sample| sample.name().to_string()),
+        sample_rate: audio.and_then(|audio| positive(audio.sample_rate)),
+        channels: audio.map(|audio| audio.ch_layout.nb_channels),
+        channel_layout,
+        bits_per_sample: audio.map(|_| bits_per_sample),
+        r_frame_rate: ratio(stream.rate(), '/'),
+        avg_frame_rate: ratio(stream.avg_frame_rate(), '/'),
+        time_base: ratio(time_base, '/'),
+        start_pts: (start_pts != ffi::AV_NOPTS_VALUE).then_some(start_pts),
+        start_time: timestamp(start_pts, time_base),
+        duration_ts: (duration_ts != ffi::AV_NOPTS_VALUE).then_some(duration_ts),
+        duration: timestamp(duration_ts, time_base),
+        bit_rate: raw.and_then(|raw| positive(raw.bit_rate)),
+        nb_frames: positive(nb_frames),
+        disposition: FfprobeDisposition(disposition),
+        tags: tags(stream.metadata()),
+    }
+}
+
+// Builds the `ffprobe -show_format -show_streams` report without shelling out
+pub fn ffprobe_media(path: &Path) -> Result<FfprobeOutput, ffmpeg_next::Error> {
+    ffmpeg_next::init()?;
+
+    let ictx = ffmpeg_next::format::input(&path)?;
+    let container_time_base = Rational::new(1, ffi::AV_TIME_BASE);
+
+    let (nb_programs, probe_score, start_time) = unsafe {
+        let raw = ictx.as_ptr();
+        ((*raw).nb_programs, (*raw).probe_score, (*raw).start_time)
+    };
+
+    let size = std::fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or_default();
+
+    let format = FfprobeFormat {
+        filename: path.to_string_lossy().to_string(),
+        nb_streams: ictx.nb_streams(),
+        nb_programs,
+        format_name: ictx.format().name().to_string(),
+        format_long_name: ictx.format().description().to_string(),
+        start_time: timestamp(start_time, container_time_base),
+        duration: (ictx.duration() > 0)
+            .then(|| timestamp(ictx.duration(), container_time_base))
+            .flatten(),
+        size: size.to_string(),
+        bit_rate: positive(ictx.bit_rate()),
+        probe_score,
+        tags: tags(ictx.metadata()),
+    };
+
+    Ok(FfprobeOutput {
+        streams: ictx.streams().map(ffprobe_stream).collect(),
+        format,
+    })
+}
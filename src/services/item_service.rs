@@ -0,0 +1,224 @@
+use crate::entities::item::ItemKind;
+use crate::entities::library::LibraryKind;
+use crate::entities::{item, media, media_stream};
+use crate::models::{ItemDetail, ItemSummary, VersionItem};
+use crate::services::tokenize;
+use sea_orm::sea_query::Query;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(FromQueryResult)]
+struct VersionCount {
+    item_id: i32,
+    count: i64,
+}
+
+// Releases of the same title differ in case and punctuation
+fn normalized(text: &str) -> String {
+    tokenize(text).join(" ")
+}
+
+// A title that is the whole file name, with no year, identifies no release:
+// `Enregistrement 2024-10-23 165821` only shares it with copies of that file
+fn is_bare_file_name(media: &media::Model) -> bool {
+    media.year.is_none()
+        && Path::new(&media.path)
+            .file_stem()
+            .is_some_and(|stem| normalized(&stem.to_string_lossy()) == normalized(&media.title))
+}
+
+fn group_key(library_kind: LibraryKind, media: &media::Model) -> (ItemKind, String) {
+    if let (Some(series_name), Some(season), Some(episode)) = (
+        &media.series_name,
+        media.season_number,
+        media.episode_number,
+    ) {
+        return (
+            ItemKind::Episode,
+            format!("episode:{}:{}:{}", normalized(series_name), season, episode),
+        );
+    }
+
+    match library_kind {
+        LibraryKind::Movies | LibraryKind::Mixed if is_bare_file_name(media) => {
+            (ItemKind::Movie, format!("file:{}", media.id))
+        }
+        LibraryKind::Movies | LibraryKind::Mixed => (
+            ItemKind::Movie,
+            format!(
+                "movie:{}:{}",
+                normalized(&media.title),
+                media.year.map(|year| year.to_string()).unwrap_or_default()
+            ),
+        ),
+        // Home videos and music are never merged
+        LibraryKind::Shows | LibraryKind::HomeVideos | LibraryKind::Music => {
+            (ItemKind::Other, format!("file:{}", media.id))
+        }
+    }
+}
+
+/// Attaches a media file to the item of its title / episode, creating the item
+/// for the first version found.
+pub async fn sync_item(
+    db: &DatabaseConnection,
+    library_kind: LibraryKind,
+    media: &media::Model,
+) -> Result<(), DbErr> {
+    let (kind, key) = group_key(library_kind, media);
+
+    let item = match item::Entity::find()
+        .filter(item::Column::GroupKey.eq(key.clone()))
+        .one(db)
+        .await?
+    {
+        Some(item) => item,
+        None => {
+            item::ActiveModel {
+                group_key: Set(key),
+                kind: Set(kind),
+                title: Set(media.title.clone()),
+                year: Set(media.year),
+                series_name: Set(media.series_name.clone()),
+                season_number: Set(media.season_number),
+                episode_number: Set(media.episode_number),
+                created_at: Set(chrono::Utc::now().naive_utc()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?
+        }
+    };
+
+    if media.item_id != Some(item.id) {
+        let mut active: media::ActiveModel = media.clone().into();
+        active.item_id = Set(Some(item.id));
+        active.update(db).await?;
+    }
+
+    Ok(())
+}
+
+// Drops items left without any version
+pub async fn prune_items(db: &DatabaseConnection) -> Result<(), DbErr> {
+    item::Entity::delete_many()
+        .filter(
+            item::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(media::Column::ItemId)
+                    .from(media::Entity)
+                    .and_where(media::Column::ItemId.is_not_null())
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn list_items(db: &DatabaseConnection) -> Result<Vec<ItemSummary>, DbErr> {
+    let counts: HashMap<i32, i64> = media::Entity::find()
+        .select_only()
+        .column_as(media::Column::ItemId, "item_id")
+        .column_as(media::Column::Id.count(), "count")
+        .filter(media::Column::ItemId.is_not_null())
+        .group_by(media::Column::ItemId)
+        .into_model::<VersionCount>()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|count| (count.item_id, count.count))
+        .collect();
+
+    let items = item::Entity::find()
+        .order_by_asc(item::Column::Title)
+        .all(db)
+        .await?;
+
+    Ok(items
+        .into_iter()
+        .map(|item| ItemSummary {
+            version_count: counts.get(&item.id).copied().unwrap_or(0),
+            id: item.id,
+            kind: item.kind,
+            title: item.title,
+            year: item.year,
+            series_name: item.series_name,
+            season_number: item.season_number,
+            episode_number: item.episode_number,
+        })
+        .collect())
+}
+
+async fn item_versions(db: &DatabaseConnection, item_id: i32) -> Result<Vec<media::Model>, DbErr> {
+    media::Entity::find()
+        .filter(media::Column::ItemId.eq(item_id))
+        .order_by_asc(media::Column::Id)
+        .all(db)
+        .await
+}
+
+pub async fn find_item(db: &DatabaseConnection, id: i32) -> Result<Option<ItemDetail>, DbErr> {
+    let Some(item) = item::Entity::find_by_id(id).one(db).await? else {
+        return Ok(None);
+    };
+
+    let versions = item_versions(db, item.id).await?;
+
+    let mut streams: HashMap<i32, Vec<media_stream::Model>> = HashMap::new();
+    for stream in media_stream::Entity::find()
+        .filter(media_stream::Column::MediaId.is_in(versions.iter().map(|media| media.id)))
+        .order_by_asc(media_stream::Column::StreamIndex)
+        .all(db)
+        .await?
+    {
+        streams.entry(stream.media_id).or_default().push(stream);
+    }
+
+    let versions = versions
+        .into_iter()
+        .map(|media| {
+            let media_streams = streams.remove(&media.id).unwrap_or_default();
+            VersionItem::new(media, &media_streams)
+        })
+        .collect();
+
+    Ok(Some(ItemDetail::new(item, versions)))
+}
+
+/// The requested version of an item, or its best present version: the largest
+/// probed picture, then the largest file.
+pub async fn find_version(
+    db: &DatabaseConnection,
+    item_id: i32,
+    version: Option<i32>,
+) -> Result<Option<media::Model>, DbErr> {
+    let versions = item_versions(db, item_id).await?;
+
+    if let Some(version) = version {
+        return Ok(versions.into_iter().find(|media| media.id == version));
+    }
+
+    let pixels: HashMap<i32, i64> = media_stream::Entity::find()
+        .filter(media_stream::Column::MediaId.is_in(versions.iter().map(|media| media.id)))
+        .filter(media_stream::Column::Medium.eq("Video"))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|stream| {
+            let pixels =
+                i64::from(stream.width.unwrap_or(0)) * i64::from(stream.height.unwrap_or(0));
+            (stream.media_id, pixels)
+        })
+        .collect();
+
+    Ok(versions
+        .into_iter()
+        .filter(|media| !media.missing)
+        .max_by_key(|media| (pixels.get(&media.id).copied().unwrap_or(0), media.size)))
+}
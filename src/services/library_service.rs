@@ -0,0 +1,123 @@
+use crate::entities::library::{LibraryKind, RootPaths, ScanOptions};
+use crate::entities::{library, media};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use std::env;
+use std::path::{Path, PathBuf};
+
+pub const VIDEO_EXTENSIONS: [&str; 6] = ["mkv", "mp4", "avi", "ts", "webm", "m4v"];
+pub const AUDIO_EXTENSIONS: [&str; 7] = ["mp3", "flac", "m4a", "ogg", "opus", "wav", "aac"];
+
+pub fn library_extensions(library: &library::Model) -> Vec<String> {
+    if !library.scan_options.extensions.is_empty() {
+        return library
+            .scan_options
+            .extensions
+            .iter()
+            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+            .collect();
+    }
+
+    let defaults: &[&str] = match library.kind {
+        LibraryKind::Music => &AUDIO_EXTENSIONS,
+        _ => &VIDEO_EXTENSIONS,
+    };
+
+    defaults
+        .iter()
+        .map(|extension| extension.to_string())
+        .collect()
+}
+
+pub fn library_roots(library: &library::Model) -> Vec<PathBuf> {
+    library.roots.0.iter().map(PathBuf::from).collect()
+}
+
+pub fn is_library_file(library: &library::Model, path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| library_extensions(library).contains(&extension.to_lowercase()))
+        .unwrap_or(false)
+}
+
+// The library owning `path`, the deepest root wins when roots are nested
+pub fn library_for_path<'a>(
+    libraries: &'a [library::Model],
+    path: &Path,
+) -> Option<&'a library::Model> {
+    libraries
+        .iter()
+        .flat_map(|library| {
+            library_roots(library)
+                .into_iter()
+                .filter(|root| path.starts_with(root))
+                .map(move |root| (root.components().count(), library))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, library)| library)
+}
+
+pub async fn list_libraries(db: &DatabaseConnection) -> Result<Vec<library::Model>, DbErr> {
+    library::Entity::find()
+        .order_by_asc(library::Column::Name)
+        .all(db)
+        .await
+}
+
+pub async fn create_library(
+    db: &DatabaseConnection,
+    name: String,
+    kind: LibraryKind,
+    roots: Vec<String>,
+    scan_options: ScanOptions,
+) -> Result<library::Model, DbErr> {
+    library::ActiveModel {
+        name: Set(name),
+        kind: Set(kind),
+        roots: Set(RootPaths(roots)),
+        scan_options: Set(scan_options),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+// Comma separated list of folders to scan, defaults to the local `./medias` tree
+fn default_roots_from_env() -> Vec<String> {
+    env::var("MEDIA_ROOTS")
+        .unwrap_or_else(|_| "./medias".to_string())
+        .split(',')
+        .map(str::trim)
+        .filter(|root| !root.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// First start after upgrading: wrap the `MEDIA_ROOTS` folders into a library
+// and attach the media rows that were scanned before libraries existed.
+pub async fn ensure_default_library(db: &DatabaseConnection) -> Result<(), DbErr> {
+    if library::Entity::find().count(db).await? > 0 {
+        return Ok(());
+    }
+
+    let library = create_library(
+        db,
+        "Medias".to_string(),
+        LibraryKind::Mixed,
+        default_roots_from_env(),
+        ScanOptions::default(),
+    )
+    .await?;
+
+    media::Entity::update_many()
+        .col_expr(media::Column::LibraryId, Expr::value(library.id))
+        .filter(media::Column::LibraryId.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
@@ -1,48 +1,162 @@
-use crate::models::CodecInfo;
-use axum::body::Body;
-use axum::http::{HeaderValue, StatusCode};
+use crate::entities::media::{Chapter, Chapters, MetadataTags};
+use crate::entities::{media, media_stream};
+use crate::models::{
+    AudioInfo, CodecInfo, ContainerInfo, MediaInfo, MediaItem, MediaPage, MediaQuery, MediaSort,
+    SortOrder, VideoInfo,
+};
+use axum::http::StatusCode;
 use ffmpeg_next::codec::Parameters;
-use ffmpeg_next::Stream;
-use std::io::SeekFrom;
-use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use ffmpeg_next::color::{Primaries, Space, TransferCharacteristic};
+use ffmpeg_next::format::stream::Disposition;
+use ffmpeg_next::format::Pixel;
+use ffmpeg_next::{codec, ffi, Stream};
+use sea_orm::sea_query::{Expr, Func, LikeExpr, Query, SimpleExpr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, Order,
+    PaginatorTrait, QueryFilter, QueryOrder, Select, Set,
+};
+use std::ffi::{c_char, c_int, CStr};
+use std::io;
+use std::path::{Path, PathBuf};
 
-pub async fn full_media_content(file: &mut File) -> Result<Body, StatusCode> {
-    let mut buffer = Vec::new();
-
-    file.read_to_end(&mut buffer)
+pub async fn find_media(db: &DatabaseConnection, id: i32) -> Result<media::Model, StatusCode> {
+    media::Entity::find_by_id(id)
+        .one(db)
         .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+pub const MAX_PAGE_SIZE: u64 = 500;
 
-    Ok(Body::from(buffer))
+// Case-insensitive equality, clients send `h.264` as often as `H.264`
+fn lower_eq(column: media::Column, value: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(column))).eq(value.to_lowercase())
 }
 
-pub fn get_content_range(start: u64, end: u64, total: u64) -> String {
-    format!("bytes {}-{}/{:?}", start, end, total)
+// `LIKE` pattern matching `value` literally between the `prefix` and `suffix`
+// wildcards, user input must not bring its own `%` and `_`
+pub fn like_literal(prefix: &str, value: &str, suffix: &str) -> LikeExpr {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    LikeExpr::new(format!("{}{}{}", prefix, escaped, suffix)).escape('\\')
 }
 
-pub async fn partial_media_content(
-    file: &mut File,
-    range_header: &HeaderValue,
-    file_size: u64,
-) -> Result<(Body, u64, u64, usize), StatusCode> {
-    let range_str = range_header.to_str().map_err(|_| StatusCode::BAD_REQUEST)?;
+// Case-insensitive match of a whole item of a comma separated column, `fr`
+// must not match `VOSTFR`
+fn list_contains(column: media::Column, value: &str) -> Condition {
+    let value = value.to_lowercase();
+    let lower = || Expr::expr(Func::lower(Expr::col(column)));
 
-    let (start, end) = parse_range_header(range_str, file_size).ok_or(StatusCode::BAD_REQUEST)?;
+    Condition::any()
+        .add(lower().eq(value.clone()))
+        .add(lower().like(like_literal("", &value, ",%")))
+        .add(lower().like(like_literal("%,", &value, "")))
+        .add(lower().like(like_literal("%,", &value, ",%")))
+}
 
-    file.seek(SeekFrom::Start(start))
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+fn filtered_media(query: &MediaQuery) -> Select<media::Entity> {
+    let mut select = media::Entity::find();
 
-    let end = end.unwrap_or(file_size - 1);
-    let chunk_size = ((end - start) + 1) as usize;
+    if let Some(library_id) = query.library {
+        select = select.filter(media::Column::LibraryId.eq(library_id));
+    }
 
-    let mut buffer = vec![0; chunk_size];
-    file.read_exact(&mut buffer)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if let Some(resolution) = &query.resolution {
+        select = select.filter(lower_eq(media::Column::Resolution, resolution));
+    }
 
-    Ok((Body::from(buffer), start, end, chunk_size))
+    if let Some(video_codec) = &query.video_codec {
+        select = select.filter(lower_eq(media::Column::VideoCodec, video_codec));
+    }
+
+    // Either an audio stream tagged with that language, or a release name marker
+    if let Some(language) = &query.audio_language {
+        select = select.filter(
+            Condition::any()
+                .add(
+                    media::Column::Id.in_subquery(
+                        Query::select()
+                            .column(media_stream::Column::MediaId)
+                            .from(media_stream::Entity)
+                            .and_where(media_stream::Column::Medium.eq("Audio"))
+                            .and_where(
+                                Expr::expr(Func::lower(Expr::col(media_stream::Column::Language)))
+                                    .eq(language.to_lowercase()),
+                            )
+                            .to_owned(),
+                    ),
+                )
+                .add(list_contains(media::Column::Languages, language)),
+        );
+    }
+
+    if let Some(watched) = query.watched {
+        select = select.filter(media::Column::Watched.eq(watched));
+    }
+
+    let order = match query.order {
+        SortOrder::Asc => Order::Asc,
+        SortOrder::Desc => Order::Desc,
+    };
+
+    let sort_column = match query.sort {
+        MediaSort::Id => media::Column::Id,
+        MediaSort::Title => media::Column::Title,
+        MediaSort::CreatedAt => media::Column::CreatedAt,
+        MediaSort::Year => media::Column::Year,
+        MediaSort::Duration => media::Column::Duration,
+    };
+
+    // The id keeps the order stable between pages when sort values are equal
+    select
+        .order_by(sort_column, order.clone())
+        .order_by(media::Column::Id, order)
+}
+
+pub async fn list_media(db: &DatabaseConnection, query: &MediaQuery) -> Result<MediaPage, DbErr> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let page = query.page.unwrap_or(1).max(1);
+
+    let paginator = filtered_media(query).paginate(db, limit);
+    let totals = paginator.num_items_and_pages().await?;
+    // Pages past the end are empty, and skipping them keeps a huge page
+    // number from overflowing the offset
+    let items = if page > totals.number_of_pages {
+        Vec::new()
+    } else {
+        paginator.fetch_page(page - 1).await?
+    };
+
+    Ok(MediaPage {
+        items: items.into_iter().map(MediaItem::from).collect(),
+        page,
+        limit,
+        total_items: totals.number_of_items,
+        total_pages: totals.number_of_pages,
+    })
+}
+
+pub async fn set_watched(
+    db: &DatabaseConnection,
+    id: i32,
+    watched: bool,
+) -> Result<Option<media::Model>, DbErr> {
+    let Some(media) = media::Entity::find_by_id(id).one(db).await? else {
+        return Ok(None);
+    };
+
+    let mut active: media::ActiveModel = media.into();
+    active.watched = Set(watched);
+
+    Ok(Some(active.update(db).await?))
 }
 
 pub fn get_medium_type(parameters: Parameters) -> String {
@@ -56,37 +170,320 @@ pub fn get_medium_type(parameters: Parameters) -> String {
     }
 }
 
+pub fn c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    Some(
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+// Stream durations are counted in time-base units, AV_NOPTS_VALUE when unknown
+fn stream_duration(stream: &Stream) -> Option<f64> {
+    let duration = stream.duration();
+
+    if duration <= 0 {
+        return None;
+    }
+
+    Some(duration as f64 * f64::from(stream.time_base()))
+}
+
+fn profile_name(codec_id: codec::Id, profile: c_int) -> Option<String> {
+    c_string(unsafe { ffi::avcodec_profile_name(codec_id.into(), profile) })
+}
+
+fn hdr_format(context: &ffi::AVCodecContext) -> Option<String> {
+    let dolby_vision = unsafe {
+        ffi::av_packet_side_data_get(
+            context.coded_side_data,
+            context.nb_coded_side_data,
+            ffi::AVPacketSideDataType::AV_PKT_DATA_DOVI_CONF,
+        )
+    };
+
+    if !dolby_vision.is_null() {
+        return Some("Dolby Vision".to_string());
+    }
+
+    match TransferCharacteristic::from(context.color_trc) {
+        TransferCharacteristic::SMPTE2084 => Some("HDR10".to_string()),
+        TransferCharacteristic::ARIB_STD_B67 => Some("HLG".to_string()),
+        _ => None,
+    }
+}
+
+fn video_info(stream: &Stream, context: &ffi::AVCodecContext) -> VideoInfo {
+    // The average rate is unset for some containers, fall back on the base rate
+    let frame_rate = [stream.avg_frame_rate(), stream.rate()]
+        .into_iter()
+        .find(|rate| rate.numerator() > 0 && rate.denominator() > 0)
+        .map(f64::from);
+
+    VideoInfo {
+        width: context.width,
+        height: context.height,
+        pixel_format: Pixel::from(context.pix_fmt)
+            .descriptor()
+            .map(|descriptor| descriptor.name().to_string()),
+        frame_rate,
+        level: (context.level > 0).then_some(context.level),
+        color_primaries: Primaries::from(context.color_primaries)
+            .name()
+            .map(str::to_string),
+        color_transfer: TransferCharacteristic::from(context.color_trc)
+            .name()
+            .map(str::to_string),
+        color_space: Space::from(context.colorspace).name().map(str::to_string),
+        hdr: hdr_format(context),
+    }
+}
+
+fn audio_info(context: &ffi::AVCodecContext) -> AudioInfo {
+    let mut layout: [c_char; 64] = [0; 64];
+    let written = unsafe {
+        ffi::av_channel_layout_describe(&context.ch_layout, layout.as_mut_ptr(), layout.len())
+    };
+
+    AudioInfo {
+        sample_rate: (context.sample_rate > 0).then_some(context.sample_rate),
+        channels: (context.ch_layout.nb_channels > 0).then_some(context.ch_layout.nb_channels),
+        channel_layout: if written > 0 {
+            c_string(layout.as_ptr())
+        } else {
+            None
+        },
+    }
+}
+
 pub fn codec_info(stream: Stream) -> CodecInfo {
     let parameters = stream.parameters();
+    let medium = parameters.medium();
+    let metadata = stream.metadata();
+    let disposition = stream.disposition();
+
+    // A codec context without an opened decoder exposes the typed parameters
+    // (pixel format, color description, channel layout) cheaply.
+    let context = codec::context::Context::from_parameters(parameters.clone()).ok();
+    let raw = context
+        .as_ref()
+        .map(|context| unsafe { &*context.as_ptr() });
 
     CodecInfo {
+        index: stream.index() as i32,
         codec_id: parameters.id().name().to_string(),
-        codec_medium: get_medium_type(parameters),
-        length: stream.duration(),
+        codec_medium: get_medium_type(parameters.clone()),
+        duration: stream_duration(&stream),
+        bit_rate: raw.map(|raw| raw.bit_rate).filter(|bit_rate| *bit_rate > 0),
+        profile: raw.and_then(|raw| profile_name(parameters.id(), raw.profile)),
+        language: metadata.get("language").map(str::to_string),
+        title: metadata.get("title").map(str::to_string),
+        default: disposition.contains(Disposition::DEFAULT),
+        forced: disposition.contains(Disposition::FORCED),
+        hearing_impaired: disposition.contains(Disposition::HEARING_IMPAIRED),
+        video: raw
+            .filter(|_| medium == ffmpeg_next::media::Type::Video)
+            .map(|raw| video_info(&stream, raw)),
+        audio: raw
+            .filter(|_| medium == ffmpeg_next::media::Type::Audio)
+            .map(audio_info),
     }
 }
 
-pub fn parse_range_header(range: &str, file_size: u64) -> Option<(u64, Option<u64>)> {
-    if !range.starts_with("bytes=") {
+pub struct ProbedMedia {
+    pub container: String,
+    pub duration: Option<f64>,
+    pub bit_rate: Option<i64>,
+    pub start_time: Option<f64>,
+    pub tags: MetadataTags,
+    pub chapters: Vec<Chapter>,
+    pub streams: Vec<CodecInfo>,
+    pub attachments: Vec<Attachment>,
+}
+
+// A file attached to the container, such as the cover art of a Matroska file
+pub struct Attachment {
+    pub index: i32,
+    pub file_name: String,
+    pub mime_type: String,
+}
+
+fn attachment(stream: Stream) -> Option<Attachment> {
+    if stream.parameters().medium() != ffmpeg_next::media::Type::Attachment {
         return None;
     }
 
-    let range = &range["bytes=".len()..];
-    let parts: Vec<&str> = range.split("-").collect();
+    let metadata = stream.metadata();
 
-    if let (Some(start), Some(end)) = (parts.get(0), parts.get(1)) {
-        let start = start.parse::<u64>().ok()?;
+    Some(Attachment {
+        index: stream.index() as i32,
+        file_name: metadata.get("filename")?.to_string(),
+        mime_type: metadata.get("mimetype").unwrap_or_default().to_string(),
+    })
+}
 
-        let end = if end.is_empty() {
-            None
-        } else {
-            Some(end.parse::<u64>().ok()?)
-        };
+// Container timestamps are expressed in AV_TIME_BASE units (microseconds)
+pub fn container_seconds(value: i64) -> f64 {
+    value as f64 / f64::from(ffi::AV_TIME_BASE)
+}
+
+pub fn probe_media(path: &Path) -> Result<ProbedMedia, ffmpeg_next::Error> {
+    ffmpeg_next::init()?;
+
+    let ictx = ffmpeg_next::format::input(&path)?;
+
+    let duration = (ictx.duration() > 0).then(|| container_seconds(ictx.duration()));
+    let start_time = unsafe { (*ictx.as_ptr()).start_time };
+
+    let chapters = ictx
+        .chapters()
+        .map(|chapter| {
+            let time_base = f64::from(chapter.time_base());
 
-        if start < file_size {
-            return Some((start, end));
+            Chapter {
+                id: chapter.id(),
+                start: chapter.start() as f64 * time_base,
+                end: chapter.end() as f64 * time_base,
+                title: chapter.metadata().get("title").map(str::to_string),
+            }
+        })
+        .collect();
+
+    Ok(ProbedMedia {
+        container: ictx.format().name().to_string(),
+        duration,
+        bit_rate: (ictx.bit_rate() > 0).then_some(ictx.bit_rate()),
+        start_time: (start_time != ffi::AV_NOPTS_VALUE).then(|| container_seconds(start_time)),
+        tags: MetadataTags(
+            ictx.metadata()
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        ),
+        chapters,
+        streams: ictx.streams().map(codec_info).collect(),
+        attachments: ictx.streams().filter_map(attachment).collect(),
+    })
+}
+
+// Copies the container level results of a probe onto a media row
+pub fn apply_probe(active: &mut media::ActiveModel, probed: &ProbedMedia) {
+    active.container = Set(Some(probed.container.clone()));
+    active.duration = Set(probed.duration);
+    active.bit_rate = Set(probed.bit_rate);
+    active.start_time = Set(probed.start_time);
+    active.tags = Set(Some(probed.tags.clone()));
+    active.chapters = Set(Some(Chapters(probed.chapters.clone())));
+}
+
+pub async fn media_streams(
+    db: &DatabaseConnection,
+    media_id: i32,
+) -> Result<Vec<media_stream::Model>, DbErr> {
+    media_stream::Entity::find()
+        .filter(media_stream::Column::MediaId.eq(media_id))
+        .order_by_asc(media_stream::Column::StreamIndex)
+        .all(db)
+        .await
+}
+
+// Replaces the stored streams of a media with the ones from its latest probe
+pub async fn store_media_streams(
+    db: &DatabaseConnection,
+    media_id: i32,
+    streams: &[CodecInfo],
+) -> Result<(), DbErr> {
+    media_stream::Entity::delete_many()
+        .filter(media_stream::Column::MediaId.eq(media_id))
+        .exec(db)
+        .await?;
+
+    if streams.is_empty() {
+        return Ok(());
+    }
+
+    media_stream::Entity::insert_many(streams.iter().map(|stream| {
+        let video = stream.video.as_ref();
+        let audio = stream.audio.as_ref();
+
+        media_stream::ActiveModel {
+            media_id: Set(media_id),
+            stream_index: Set(stream.index),
+            codec: Set(stream.codec_id.clone()),
+            medium: Set(stream.codec_medium.clone()),
+            duration: Set(stream.duration),
+            bit_rate: Set(stream.bit_rate),
+            profile: Set(stream.profile.clone()),
+            language: Set(stream.language.clone()),
+            title: Set(stream.title.clone()),
+            is_default: Set(stream.default),
+            is_forced: Set(stream.forced),
+            is_hearing_impaired: Set(stream.hearing_impaired),
+            width: Set(video.map(|video| video.width)),
+            height: Set(video.map(|video| video.height)),
+            pixel_format: Set(video.and_then(|video| video.pixel_format.clone())),
+            frame_rate: Set(video.and_then(|video| video.frame_rate)),
+            level: Set(video.and_then(|video| video.level)),
+            color_primaries: Set(video.and_then(|video| video.color_primaries.clone())),
+            color_transfer: Set(video.and_then(|video| video.color_transfer.clone())),
+            color_space: Set(video.and_then(|video| video.color_space.clone())),
+            hdr: Set(video.and_then(|video| video.hdr.clone())),
+            channels: Set(audio.and_then(|audio| audio.channels)),
+            sample_rate: Set(audio.and_then(|audio| audio.sample_rate)),
+            channel_layout: Set(audio.and_then(|audio| audio.channel_layout.clone())),
+            ..Default::default()
         }
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+// Container and streams as stored at scan time, media that was never scanned
+// is probed once here and the results are kept. A file that is gone, or a row
+// deleted meanwhile, is reported as `RecordNotFound`.
+pub async fn media_info(db: &DatabaseConnection, media: media::Model) -> Result<MediaInfo, DbErr> {
+    let stored = media_streams(db, media.id).await?;
+
+    if media.container.is_some() && !stored.is_empty() {
+        return Ok(MediaInfo {
+            name: media.title.clone(),
+            container: ContainerInfo::from(&media),
+            codecs: stored.into_iter().map(CodecInfo::from).collect(),
+        });
     }
 
-    None
+    let metadata = tokio::fs::metadata(&media.path)
+        .await
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => DbErr::RecordNotFound(media.path.clone()),
+            _ => DbErr::Custom(e.to_string()),
+        })?;
+
+    let path = PathBuf::from(&media.path);
+    let probed = tokio::task::spawn_blocking(move || probe_media(&path))
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    let mut active: media::ActiveModel = media.into();
+    active.size = Set(metadata.len() as i64);
+    apply_probe(&mut active, &probed);
+    let media = active.update(db).await.map_err(|e| match e {
+        DbErr::RecordNotUpdated => DbErr::RecordNotFound(String::from("media")),
+        e => e,
+    })?;
+
+    store_media_streams(db, media.id, &probed.streams).await?;
+
+    Ok(MediaInfo {
+        name: media.title.clone(),
+        container: ContainerInfo::from(&media),
+        codecs: probed.streams,
+    })
 }
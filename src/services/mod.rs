@@ -1,5 +1,29 @@
+mod artwork_service;
+mod catalog_service;
+mod duplicate_service;
+mod ffprobe_service;
+mod item_service;
+mod library_service;
 mod media_service;
+mod scanner_service;
+mod search_service;
+mod stream_service;
+mod thumbnail_service;
 mod transcode_media_service;
+mod trickplay_service;
+mod watcher_service;
 
+pub use artwork_service::*;
+pub use catalog_service::*;
+pub use duplicate_service::*;
+pub use ffprobe_service::*;
+pub use item_service::*;
+pub use library_service::*;
 pub use media_service::*;
+pub use scanner_service::*;
+pub use search_service::*;
+pub use stream_service::*;
+pub use thumbnail_service::*;
 pub use transcode_media_service::*;
+pub use trickplay_service::*;
+pub use watcher_service::*;
@@ -0,0 +1,575 @@
+use crate::entities::library::LibraryKind;
+use crate::entities::media::{ExternalIds, Rating, Ratings};
+use crate::entities::{artwork, episode, library, media, media_stream};
+use crate::models::ScanReport;
+use crate::parsers::{
+    parse_episode, parse_movie, parse_nfo, parse_release_tags, EpisodeInfo, NfoInfo, NfoKind,
+};
+use crate::services::{
+    apply_probe, clear_trickplay, ensure_thumbnail, fingerprint_file, is_library_file,
+    library_for_path, library_roots, like_literal, list_libraries, probe_media, prune_items,
+    prune_tv_catalog, store_media_streams, sync_artwork, sync_episode, sync_item,
+};
+use chrono::{DateTime, NaiveDateTime, SubsecRound, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    Set,
+};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+pub enum IndexOutcome {
+    Added,
+    Updated,
+    Unchanged,
+    Skipped,
+}
+
+pub struct FileStat {
+    pub size: i64,
+    pub modified_at: Option<NaiveDateTime>,
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('.'))
+        .unwrap_or(false)
+}
+
+pub fn collect_library_files(
+    library: &library::Model,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if !library.scan_options.include_hidden && is_hidden(&path) {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_library_files(library, &path, files)?;
+        } else if is_library_file(library, &path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn file_stat(path: &Path) -> io::Result<FileStat> {
+    let metadata = fs::metadata(path)?;
+
+    Ok(FileStat {
+        size: metadata.len() as i64,
+        // Truncated to whole seconds so the value survives a database round-trip
+        modified_at: metadata
+            .modified()
+            .ok()
+            .map(|modified| DateTime::<Utc>::from(modified).naive_utc().trunc_subsecs(0)),
+    })
+}
+
+// "Foundation S02E03", or "Show S01E01-E02" for multi-episode files
+pub fn episode_title(episode: &EpisodeInfo) -> String {
+    match episode.episode_end {
+        Some(end) => format!(
+            "{} S{:02}E{:02}-E{:02}",
+            episode.series, episode.season, episode.episode, end
+        ),
+        None => format!(
+            "{} S{:02}E{:02}",
+            episode.series, episode.season, episode.episode
+        ),
+    }
+}
+
+pub fn title_from_path(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+fn read_nfo(path: &Path) -> Option<NfoInfo> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|xml| parse_nfo(&xml))
+}
+
+// Whether `path` is the only media file of its folder
+fn is_alone_in_folder(library: &library::Model, path: &Path) -> bool {
+    let Some(Ok(entries)) = path.parent().map(fs::read_dir) else {
+        return false;
+    };
+
+    entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|entry| entry.is_file() && is_library_file(library, entry))
+        .take(2)
+        .count()
+        == 1
+}
+
+// `<name>.nfo` next to the file, or for a movie the folder wide `movie.nfo`.
+// As in Kodi the latter only describes a folder holding that single movie,
+// the files of a flat movie folder would all get the same title otherwise.
+fn find_media_nfo(library: &library::Model, path: &Path, is_episode: bool) -> Option<NfoInfo> {
+    let nfo = read_nfo(&path.with_extension("nfo")).or_else(|| {
+        if is_episode || !is_alone_in_folder(library, path) {
+            None
+        } else {
+            read_nfo(&path.parent()?.join("movie.nfo"))
+        }
+    })?;
+
+    (nfo.kind != NfoKind::TvShow).then_some(nfo)
+}
+
+// `tvshow.nfo` sits in the show folder, above the season folder when there is one
+fn find_show_nfo(path: &Path) -> Option<NfoInfo> {
+    path.ancestors()
+        .skip(1)
+        .take(2)
+        .find_map(|dir| read_nfo(&dir.join("tvshow.nfo")))
+        .filter(|nfo| nfo.kind == NfoKind::TvShow)
+}
+
+fn join_list(items: &[String]) -> Option<String> {
+    Some(items.join(",")).filter(|list| !list.is_empty())
+}
+
+// Runs `apply_local_metadata` on the blocking pool, looking for NFO files
+// reads them and lists the media folder
+async fn with_local_metadata(
+    mut active: media::ActiveModel,
+    library: &library::Model,
+    path: &Path,
+) -> Result<media::ActiveModel, DbErr> {
+    let library = library.clone();
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        apply_local_metadata(&mut active, &library, &path);
+        active
+    })
+    .await
+    .map_err(|e| DbErr::Custom(e.to_string()))
+}
+
+// Structured fields derived from the file name and from NFO sidecar files,
+// refreshed on every scan so that parser improvements and edited NFO files
+// reach rows whose media file did not change. NFO values take precedence.
+// Home videos and music keep their file name, release naming rules only make
+// sense for the movie and show libraries.
+fn apply_local_metadata(active: &mut media::ActiveModel, library: &library::Model, path: &Path) {
+    let kind = library.kind;
+    let mut episode = match kind {
+        LibraryKind::Shows | LibraryKind::Mixed => parse_episode(path),
+        _ => None,
+    };
+    let movie = match (kind, &episode) {
+        (LibraryKind::Movies | LibraryKind::Mixed, None) => parse_movie(path),
+        _ => None,
+    };
+    let tags = match kind {
+        LibraryKind::Movies | LibraryKind::Shows | LibraryKind::Mixed => path
+            .file_stem()
+            .map(|stem| parse_release_tags(&stem.to_string_lossy()))
+            .unwrap_or_default(),
+        LibraryKind::HomeVideos | LibraryKind::Music => Default::default(),
+    };
+
+    let nfo = find_media_nfo(library, path, episode.is_some());
+    let show = episode.as_ref().and_then(|_| find_show_nfo(path));
+
+    // Episode numbers of the NFO file override the ones of the file name
+    if let (Some(episode), Some(nfo)) = (&mut episode, &nfo) {
+        if nfo.kind == NfoKind::Episode {
+            episode.season = nfo.season.unwrap_or(episode.season);
+
+            if let Some(number) = nfo.episode {
+                episode.episode = number;
+                episode.episode_end = episode.episode_end.filter(|end| *end > number);
+            }
+        }
+    }
+
+    let title = match (&episode, &movie) {
+        (Some(episode), _) => episode_title(episode),
+        (None, Some(movie)) => movie.title.clone(),
+        (None, None) => title_from_path(path),
+    };
+    let series_name = episode.as_ref().map(|episode| {
+        show.as_ref()
+            .and_then(|show| show.title.clone())
+            .unwrap_or_else(|| episode.series.clone())
+    });
+
+    // Episode files fall back on the show wide genres, studios and plot
+    let genres = nfo
+        .iter()
+        .chain(show.iter())
+        .map(|nfo| nfo.genres.as_slice())
+        .find(|genres| !genres.is_empty())
+        .unwrap_or_default();
+    let studios = nfo
+        .iter()
+        .chain(show.iter())
+        .map(|nfo| nfo.studios.as_slice())
+        .find(|studios| !studios.is_empty())
+        .unwrap_or_default();
+
+    active.title.set_if_not_equals(
+        nfo.as_ref()
+            .and_then(|nfo| nfo.title.clone())
+            .unwrap_or(title),
+    );
+    active.series_name.set_if_not_equals(series_name);
+    active
+        .season_number
+        .set_if_not_equals(episode.as_ref().map(|episode| episode.season));
+    active
+        .episode_number
+        .set_if_not_equals(episode.as_ref().map(|episode| episode.episode));
+    active
+        .episode_number_end
+        .set_if_not_equals(episode.as_ref().and_then(|episode| episode.episode_end));
+    active.year.set_if_not_equals(
+        nfo.as_ref()
+            .and_then(|nfo| nfo.year)
+            .or_else(|| movie.as_ref().and_then(|movie| movie.year)),
+    );
+    active.source.set_if_not_equals(tags.source);
+    active.resolution.set_if_not_equals(tags.resolution);
+    active.video_codec.set_if_not_equals(tags.video_codec);
+    active.audio_codec.set_if_not_equals(tags.audio_codec);
+    active
+        .languages
+        .set_if_not_equals(join_list(&tags.languages));
+    active.release_group.set_if_not_equals(tags.release_group);
+
+    active
+        .original_title
+        .set_if_not_equals(nfo.as_ref().and_then(|nfo| nfo.original_title.clone()));
+    active.overview.set_if_not_equals(
+        nfo.iter()
+            .chain(show.iter())
+            .find_map(|nfo| nfo.plot.clone()),
+    );
+    active
+        .cast_members
+        .set_if_not_equals(nfo.as_ref().and_then(|nfo| join_list(&nfo.cast)));
+    active.genres.set_if_not_equals(join_list(genres));
+    active.studios.set_if_not_equals(join_list(studios));
+    active.ratings.set_if_not_equals(nfo.as_ref().map(|nfo| {
+        Ratings(
+            nfo.ratings
+                .iter()
+                .map(|rating| Rating {
+                    name: rating.name.clone(),
+                    value: rating.value,
+                    max: rating.max,
+                    votes: rating.votes,
+                })
+                .collect(),
+        )
+    }));
+    active
+        .runtime
+        .set_if_not_equals(nfo.as_ref().and_then(|nfo| nfo.runtime));
+    active
+        .external_ids
+        .set_if_not_equals(nfo.as_ref().map(|nfo| ExternalIds(nfo.ids.clone())));
+}
+
+/// Inserts or refreshes the media row for a single file, probing it only when
+/// it is new or its size / modification time changed since the last scan.
+pub async fn index_file(
+    db: &DatabaseConnection,
+    library: &library::Model,
+    path: &Path,
+) -> Result<IndexOutcome, DbErr> {
+    let path_str = path.to_string_lossy().to_string();
+
+    let stat = match file_stat(path) {
+        Ok(stat) => stat,
+        Err(e) => {
+            eprintln!("could not stat {}: {}", path_str, e);
+            return Ok(IndexOutcome::Skipped);
+        }
+    };
+
+    let existing = media::Entity::find()
+        .filter(media::Column::Path.eq(path_str.clone()))
+        .one(db)
+        .await?;
+
+    let (outcome, model, probed) = match existing {
+        Some(existing)
+            if !existing.missing
+                && existing.size == stat.size
+                && existing.modified_at == stat.modified_at =>
+        {
+            let mut active: media::ActiveModel = existing.clone().into();
+            active.library_id.set_if_not_equals(Some(library.id));
+            let active = with_local_metadata(active, library, path).await?;
+
+            if active.is_changed() {
+                (IndexOutcome::Updated, active.update(db).await?, None)
+            } else {
+                (IndexOutcome::Unchanged, existing, None)
+            }
+        }
+        existing => {
+            let probe_path = path.to_path_buf();
+            let probed = tokio::task::spawn_blocking(move || probe_media(&probe_path))
+                .await
+                .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+            let probed = match probed {
+                Ok(probed) => probed,
+                Err(e) => {
+                    eprintln!("could not probe {}: {}", path_str, e);
+                    return Ok(IndexOutcome::Skipped);
+                }
+            };
+
+            let fingerprint_path = path.to_path_buf();
+            let fingerprint =
+                tokio::task::spawn_blocking(move || fingerprint_file(&fingerprint_path))
+                    .await
+                    .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+            let fingerprint = match fingerprint {
+                Ok(fingerprint) => Some(fingerprint),
+                Err(e) => {
+                    eprintln!("could not fingerprint {}: {}", path_str, e);
+                    None
+                }
+            };
+
+            match existing {
+                Some(existing) => {
+                    let mut active: media::ActiveModel = existing.into();
+                    active.size = Set(stat.size);
+                    active.modified_at = Set(stat.modified_at);
+                    apply_probe(&mut active, &probed);
+                    active.fingerprint = Set(fingerprint);
+                    active.missing = Set(false);
+                    active.library_id = Set(Some(library.id));
+                    let active = with_local_metadata(active, library, path).await?;
+
+                    (
+                        IndexOutcome::Updated,
+                        active.update(db).await?,
+                        Some(probed),
+                    )
+                }
+                None => {
+                    let mut active = media::ActiveModel {
+                        path: Set(path_str),
+                        created_at: Set(Utc::now().naive_utc()),
+                        size: Set(stat.size),
+                        modified_at: Set(stat.modified_at),
+                        fingerprint: Set(fingerprint),
+                        library_id: Set(Some(library.id)),
+                        ..Default::default()
+                    };
+                    apply_probe(&mut active, &probed);
+                    let active = with_local_metadata(active, library, path).await?;
+
+                    (IndexOutcome::Added, active.insert(db).await?, Some(probed))
+                }
+            }
+        }
+    };
+
+    if let Some(probed) = &probed {
+        store_media_streams(db, model.id, &probed.streams).await?;
+        clear_trickplay(model.id);
+    }
+
+    sync_artwork(
+        db,
+        &model,
+        probed.as_ref().map(|probed| probed.attachments.as_slice()),
+    )
+    .await?;
+    ensure_thumbnail(db, &model, probed.is_some()).await?;
+
+    sync_episode(db, &model).await?;
+    sync_item(db, library.kind, &model).await?;
+
+    Ok(outcome)
+}
+
+// Matches the row for `path` itself or, when `path` is a folder, every row below it.
+// `%` and `_` in the path are literal, `a_b` must not match `axb`.
+fn path_condition(path: &Path) -> Condition {
+    let path_str = path.to_string_lossy().to_string();
+
+    Condition::any()
+        .add(media::Column::Path.eq(path_str.clone()))
+        .add(media::Column::Path.like(like_literal("", &format!("{}/", path_str), "%")))
+}
+
+pub async fn mark_missing(db: &DatabaseConnection, path: &Path) -> Result<u64, DbErr> {
+    let result = media::Entity::update_many()
+        .col_expr(media::Column::Missing, Expr::value(true))
+        .filter(path_condition(path))
+        .filter(media::Column::Missing.eq(false))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
+
+// Not left to the foreign keys, SQLite databases are created without them.
+// The generated thumbnail and the trickplay previews are removed from the cache.
+pub async fn delete_media(db: &DatabaseConnection, id: i32) -> Result<(), DbErr> {
+    let generated = artwork::Entity::find()
+        .filter(artwork::Column::MediaId.eq(id))
+        .filter(artwork::Column::IsGenerated.eq(true))
+        .all(db)
+        .await?;
+
+    media_stream::Entity::delete_many()
+        .filter(media_stream::Column::MediaId.eq(id))
+        .exec(db)
+        .await?;
+    artwork::Entity::delete_many()
+        .filter(artwork::Column::MediaId.eq(id))
+        .exec(db)
+        .await?;
+    episode::Entity::delete_many()
+        .filter(episode::Column::MediaId.eq(id))
+        .exec(db)
+        .await?;
+    media::Entity::delete_by_id(id).exec(db).await?;
+
+    for thumbnail in generated {
+        if let Err(e) = fs::remove_file(&thumbnail.path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                eprintln!("could not remove {}: {}", thumbnail.path, e);
+            }
+        }
+    }
+    clear_trickplay(id);
+
+    Ok(())
+}
+
+pub async fn rename_media(db: &DatabaseConnection, from: &Path, to: &Path) -> Result<u64, DbErr> {
+    let from_str = from.to_string_lossy().to_string();
+    let to_str = to.to_string_lossy().to_string();
+
+    let rows = media::Entity::find()
+        .filter(path_condition(from))
+        .all(db)
+        .await?;
+    let renamed = rows.len() as u64;
+
+    for row in rows {
+        let new_path = format!("{}{}", to_str, &row.path[from_str.len()..]);
+
+        // The move replaced the file another row still points at
+        let replaced = media::Entity::find()
+            .filter(media::Column::Path.eq(new_path.clone()))
+            .filter(media::Column::Id.ne(row.id))
+            .one(db)
+            .await?;
+
+        if let Some(replaced) = replaced {
+            delete_media(db, replaced.id).await?;
+        }
+
+        let mut active: media::ActiveModel = row.into();
+        active.path = Set(new_path);
+        active.update(db).await?;
+    }
+
+    Ok(renamed)
+}
+
+pub async fn run_library_scan(
+    db: &DatabaseConnection,
+    libraries: &[library::Model],
+) -> Result<ScanReport, DbErr> {
+    let mut report = ScanReport::default();
+
+    // Every library, not only the scanned ones, to find the owner of files
+    // under nested roots
+    let all_libraries = list_libraries(db).await?;
+
+    for library in libraries {
+        let mut seen_paths = HashSet::new();
+        let mut scanned_roots = Vec::new();
+
+        for root in library_roots(library) {
+            let walk_library = library.clone();
+            let walk_root = root.clone();
+            let files = tokio::task::spawn_blocking(move || {
+                let mut files = Vec::new();
+                collect_library_files(&walk_library, &walk_root, &mut files).map(|_| files)
+            })
+            .await
+            .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+            // An unreadable root (e.g. an unmounted drive) must not wipe its rows
+            let files = match files {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("could not scan {}: {}", root.display(), e);
+                    continue;
+                }
+            };
+
+            scanned_roots.push(root);
+
+            for path in files {
+                seen_paths.insert(path.to_string_lossy().to_string());
+
+                // Files under a nested root of another library belong to the
+                // deepest one, as in the watcher
+                let owner = library_for_path(&all_libraries, &path);
+                if owner.is_some_and(|owner| owner.id != library.id) {
+                    continue;
+                }
+
+                match index_file(db, library, &path).await? {
+                    IndexOutcome::Added => report.added += 1,
+                    IndexOutcome::Updated => report.updated += 1,
+                    IndexOutcome::Unchanged | IndexOutcome::Skipped => {}
+                }
+            }
+        }
+
+        // Files that disappeared are flagged as missing rather than deleted
+        let present_rows = media::Entity::find()
+            .filter(media::Column::LibraryId.eq(library.id))
+            .filter(media::Column::Missing.eq(false))
+            .all(db)
+            .await?;
+
+        for row in present_rows {
+            let in_scanned_root = scanned_roots
+                .iter()
+                .any(|root| Path::new(&row.path).starts_with(root));
+
+            if !in_scanned_root || seen_paths.contains(&row.path) {
+                continue;
+            }
+
+            report.removed += mark_missing(db, Path::new(&row.path)).await? as u32;
+        }
+    }
+
+    prune_tv_catalog(db).await?;
+    prune_items(db).await?;
+
+    Ok(report)
+}
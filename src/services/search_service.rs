@@ -0,0 +1,274 @@
+use crate::entities::media;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter, QuerySelect, Statement,
+};
+use std::collections::HashMap;
+
+pub const DEFAULT_SEARCH_LIMIT: u64 = 20;
+
+// Below this trigram word similarity a title is not considered a typo of the query
+const MIN_WORD_SIMILARITY: f64 = 0.4;
+
+#[derive(FromQueryResult)]
+struct SearchHit {
+    id: i32,
+    score: f64,
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Every word of the query must match as a prefix: "kings serv" -> "kings:* & serv:*"
+fn prefix_tsquery(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("{}:*", token))
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+async fn search_postgres(
+    db: &DatabaseConnection,
+    query: &str,
+    tokens: &[String],
+    limit: u64,
+) -> Result<Vec<SearchHit>, DbErr> {
+    let statement = Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        r#"
+        WITH q AS (SELECT $1::text AS text, to_tsquery('simple', $2) AS query)
+        SELECT media.id,
+               (ts_rank(media.search_vector, q.query) * 2
+                + greatest(
+                    word_similarity(q.text, media.title),
+                    word_similarity(q.text, coalesce(media.series_name, ''))
+                ))::float8 AS score
+        FROM media, q
+        WHERE NOT media.missing
+          AND (media.search_vector @@ q.query
+               OR word_similarity(q.text, media.title) > $3
+               OR word_similarity(q.text, coalesce(media.series_name, '')) > $3)
+        ORDER BY score DESC, media.id
+        LIMIT $4
+        "#,
+        [
+            query.into(),
+            prefix_tsquery(tokens).into(),
+            MIN_WORD_SIMILARITY.into(),
+            (limit as i64).into(),
+        ],
+    );
+
+    SearchHit::find_by_statement(statement).all(db).await
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// Exact words beat prefixes, which beat words within a couple of typos
+fn token_score(query_token: &str, document_token: &str) -> f64 {
+    if document_token == query_token {
+        return 1.0;
+    }
+
+    if document_token.starts_with(query_token) {
+        return 0.9;
+    }
+
+    let allowed_typos = match query_token.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+
+    let distance = levenshtein(query_token, document_token);
+
+    if distance <= allowed_typos {
+        0.8 - 0.2 * (distance - 1) as f64
+    } else {
+        0.0
+    }
+}
+
+fn field_score(query_token: &str, field: &[String]) -> f64 {
+    field
+        .iter()
+        .map(|document_token| token_score(query_token, document_token))
+        .fold(0.0, f64::max)
+}
+
+type SearchDocument = (i32, String, Option<String>, Option<String>, Option<String>);
+
+// Embedded fallback for databases without full-text search: every query word
+// has to match one of the weighted fields, typos and prefixes included.
+fn score_document(tokens: &[String], document: &SearchDocument) -> Option<f64> {
+    let (_, title, series_name, overview, cast_members) = document;
+
+    let fields = [
+        (tokenize(title), 1.0),
+        (tokenize(series_name.as_deref().unwrap_or_default()), 1.0),
+        (tokenize(cast_members.as_deref().unwrap_or_default()), 0.6),
+        (tokenize(overview.as_deref().unwrap_or_default()), 0.3),
+    ];
+
+    let mut total = 0.0;
+
+    for token in tokens {
+        let best = fields
+            .iter()
+            .map(|(field, weight)| field_score(token, field) * weight)
+            .fold(0.0, f64::max);
+
+        if best == 0.0 {
+            return None;
+        }
+
+        total += best;
+    }
+
+    Some(total / tokens.len() as f64)
+}
+
+async fn search_embedded(
+    db: &DatabaseConnection,
+    tokens: &[String],
+    limit: u64,
+) -> Result<Vec<SearchHit>, DbErr> {
+    let documents: Vec<SearchDocument> = media::Entity::find()
+        .select_only()
+        .columns([
+            media::Column::Id,
+            media::Column::Title,
+            media::Column::SeriesName,
+            media::Column::Overview,
+            media::Column::CastMembers,
+        ])
+        .filter(media::Column::Missing.eq(false))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut hits: Vec<SearchHit> = documents
+        .iter()
+        .filter_map(|document| {
+            score_document(tokens, document).map(|score| SearchHit {
+                id: document.0,
+                score,
+            })
+        })
+        .collect();
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+    hits.truncate(limit as usize);
+
+    Ok(hits)
+}
+
+// Matching media ranked by relevance, best first
+pub async fn search_media(
+    db: &DatabaseConnection,
+    query: &str,
+    limit: u64,
+) -> Result<Vec<(f64, media::Model)>, DbErr> {
+    let tokens = tokenize(query);
+
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let hits = match db.get_database_backend() {
+        DatabaseBackend::Postgres => search_postgres(db, query, &tokens, limit).await?,
+        _ => search_embedded(db, &tokens, limit).await?,
+    };
+
+    let mut models: HashMap<i32, media::Model> = media::Entity::find()
+        .filter(media::Column::Id.is_in(hits.iter().map(|hit| hit.id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| (model.id, model))
+        .collect();
+
+    Ok(hits
+        .into_iter()
+        .filter_map(|hit| models.remove(&hit.id).map(|model| (hit.score, model)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, title: &str) -> Option<f64> {
+        let document = (1, title.to_string(), None, None, None);
+        score_document(&tokenize(query), &document)
+    }
+
+    #[test]
+    fn exact_title() {
+        assert_eq!(score("Kingsman", "Kingsman"), Some(1.0));
+        assert_eq!(score("the matrix", "The Matrix"), Some(1.0));
+    }
+
+    #[test]
+    fn prefix_of_a_word() {
+        assert_eq!(score("kings", "Kingsman"), Some(0.9));
+    }
+
+    #[test]
+    fn one_typo() {
+        assert_eq!(score("kingsmen", "Kingsman"), Some(0.8));
+    }
+
+    #[test]
+    fn exact_beats_prefix_beats_typo() {
+        let exact = score("kingsman", "Kingsman").unwrap();
+        let prefix = score("kingsman", "Kingsmanship").unwrap();
+        let typo = score("kingsman", "Kingsmen").unwrap();
+
+        assert!(exact > prefix && prefix > typo);
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(score("zebra", "Kingsman"), None);
+        // Short words allow no typo
+        assert_eq!(score("cat", "Car"), None);
+        // Every word of the query has to match
+        assert_eq!(score("kingsman zebra", "Kingsman"), None);
+    }
+
+    #[test]
+    fn weaker_fields_score_lower() {
+        let document = (
+            1,
+            String::from("Kingsman"),
+            None,
+            Some(String::from("A spy story")),
+            Some(String::from("Colin Firth")),
+        );
+
+        assert_eq!(score_document(&tokenize("firth"), &document), Some(0.6));
+        assert_eq!(score_document(&tokenize("spy"), &document), Some(0.3));
+    }
+}
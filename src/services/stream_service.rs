@@ -0,0 +1,834 @@
+use axum::body::{Body, Bytes};
+use axum::http::{header, HeaderMap, StatusCode};
+use futures::{stream, StreamExt, TryStreamExt};
+use mime_guess::from_path;
+use std::fs::Metadata;
+use std::io::SeekFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+// Read buffer of streamed files, memory per request stays at this size
+// whatever the size of the file or of the requested range
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+// Range sets still holding more ranges than this once coalesced are
+// answered with the whole file, each part costs a seek and a header so such
+// sets are mostly abuse
+pub const MAX_RANGES: usize = 16;
+
+static BOUNDARY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Inclusive byte positions, always within the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    // Malformed header or unknown unit, RFC 7233 has it ignored and the
+    // whole file served
+    Invalid,
+    // Well formed but no range overlaps the file, answered with a 416
+    Unsatisfiable,
+}
+
+// Content type of the demuxer reported by the probe. ffmpeg names some
+// demuxers after every format they read, such as `matroska,webm`.
+fn container_mime_type(container: &str) -> Option<&'static str> {
+    let mime_type = match container.split(',').next()? {
+        "matroska" => "video/x-matroska",
+        "mov" => "video/mp4",
+        "mpegts" => "video/mp2t",
+        "mpeg" => "video/mpeg",
+        "avi" => "video/x-msvideo",
+        "asf" => "video/x-ms-asf",
+        "flv" => "video/x-flv",
+        "ogg" => "video/ogg",
+        _ => return None,
+    };
+
+    Some(mime_type)
+}
+
+// Content type of a media file from its extension, or from its probed
+// container when the extension is unknown or not a media one
+pub fn media_content_type(path: &str, container: Option<&str>) -> String {
+    let guessed = from_path(path)
+        .iter()
+        .find(|mime| mime.type_() == "video" || mime.type_() == "audio");
+
+    match guessed {
+        // Registered for `.ts` and `.m2ts` but unknown to browsers and players
+        Some(mime) if mime.essence_str() == "video/vnd.dlna.mpeg-tts" => String::from("video/mp2t"),
+        Some(mime) => mime.to_string(),
+        None => container
+            .and_then(container_mime_type)
+            .unwrap_or("application/octet-stream")
+            .to_string(),
+    }
+}
+
+// Validators of a served file, sent with every response so clients can
+// revalidate their cache and resume downloads safely
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileValidators {
+    // Strong entity tag, quotes included
+    pub etag: String,
+    // Truncated to the second like the `Last-Modified` header
+    pub last_modified: Option<SystemTime>,
+}
+
+impl FileValidators {
+    pub fn last_modified_header(&self) -> Option<String> {
+        self.last_modified.map(httpdate::fmt_http_date)
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+// The ETag changes whenever the file is replaced (inode), truncated or
+// appended to (size) or rewritten in place (mtime)
+pub fn file_validators(metadata: &Metadata) -> FileValidators {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+
+    FileValidators {
+        etag: format!(
+            "\"{:x}-{:x}-{:x}\"",
+            inode(metadata),
+            metadata.len(),
+            modified.map_or(0, |modified| modified.as_nanos())
+        ),
+        last_modified: modified
+            .map(|modified| UNIX_EPOCH + Duration::from_secs(modified.as_secs())),
+    }
+}
+
+// Opaque part of an entity tag, and whether it is weak
+fn parse_entity_tag(tag: &str) -> Option<(&str, bool)> {
+    let (tag, weak) = match tag.strip_prefix("W/") {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+
+    let opaque = tag.strip_prefix('"')?.strip_suffix('"')?;
+
+    if opaque.contains('"') {
+        return None;
+    }
+
+    Some((opaque, weak))
+}
+
+fn header_value(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn header_date(headers: &HeaderMap, name: header::HeaderName) -> Option<SystemTime> {
+    header_value(headers, name).and_then(|value| httpdate::parse_http_date(value.trim()).ok())
+}
+
+// RFC 7232 evaluation of `If-None-Match`, or of `If-Modified-Since` when the
+// former is absent, for a GET or HEAD request. `true` means a 304.
+pub fn is_not_modified(headers: &HeaderMap, validators: &FileValidators) -> bool {
+    let current = parse_entity_tag(&validators.etag).map(|(opaque, _)| opaque);
+
+    if let Some(if_none_match) = header_value(headers, header::IF_NONE_MATCH) {
+        // Weak comparison, `W/"x"` matches `"x"`
+        return if_none_match.split(',').map(str::trim).any(|tag| {
+            tag == "*" || parse_entity_tag(tag).is_some_and(|(opaque, _)| Some(opaque) == current)
+        });
+    }
+
+    match (
+        header_date(headers, header::IF_MODIFIED_SINCE),
+        validators.last_modified,
+    ) {
+        (Some(since), Some(last_modified)) => last_modified <= since,
+        _ => false,
+    }
+}
+
+// Whether the `Range` header applies. Without `If-Range` it always does,
+// otherwise only when the client's copy is still the current file, so a
+// resumed download never splices two versions together.
+pub fn if_range_matches(headers: &HeaderMap, validators: &FileValidators) -> bool {
+    let Some(if_range) = headers.get(header::IF_RANGE) else {
+        return true;
+    };
+
+    let Ok(if_range) = if_range.to_str() else {
+        return false;
+    };
+    let if_range = if_range.trim();
+
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        // Strong comparison, weak tags never match
+        return match (
+            parse_entity_tag(if_range),
+            parse_entity_tag(&validators.etag),
+        ) {
+            (Some((tag, false)), Some((current, false))) => tag == current,
+            _ => false,
+        };
+    }
+
+    match (
+        httpdate::parse_http_date(if_range).ok(),
+        validators.last_modified,
+    ) {
+        (Some(date), Some(last_modified)) => date == last_modified,
+        _ => false,
+    }
+}
+
+pub fn full_media_content(file: File) -> Body {
+    Body::from_stream(ReaderStream::with_capacity(file, STREAM_BUFFER_SIZE))
+}
+
+pub fn get_content_range(start: u64, end: u64, total: u64) -> String {
+    format!("bytes {}-{}/{:?}", start, end, total)
+}
+
+// `Content-Range` of a 416 response
+pub fn unsatisfied_content_range(total: u64) -> String {
+    format!("bytes */{}", total)
+}
+
+pub async fn partial_media_content(mut file: File, range: ByteRange) -> Result<Body, StatusCode> {
+    file.seek(SeekFrom::Start(range.start))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Body::from_stream(ReaderStream::with_capacity(
+        file.take(range.length()),
+        STREAM_BUFFER_SIZE,
+    )))
+}
+
+// Sorts the ranges and merges the overlapping or adjacent ones, so no byte
+// is sent twice and parts come in file order
+pub fn coalesce_ranges(mut ranges: Vec<ByteRange>) -> Vec<ByteRange> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut coalesced: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+
+    for range in ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => {
+                last.end = last.end.max(range.end);
+            }
+            _ => coalesced.push(range),
+        }
+    }
+
+    coalesced
+}
+
+// Unique per response, and made of characters that never need quoting in
+// the `Content-Type` parameter
+pub fn multipart_boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default();
+    let counter = BOUNDARY_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{:016x}{:08x}", nanos, counter)
+}
+
+pub fn multipart_content_type(boundary: &str) -> String {
+    format!("multipart/byteranges; boundary={}", boundary)
+}
+
+// Delimiter and headers preceding the bytes of one part
+fn multipart_part_header(
+    boundary: &str,
+    content_type: &str,
+    range: ByteRange,
+    total: u64,
+) -> String {
+    format!(
+        "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+        boundary,
+        content_type,
+        get_content_range(range.start, range.end, total)
+    )
+}
+
+fn multipart_closing(boundary: &str) -> String {
+    format!("\r\n--{}--\r\n", boundary)
+}
+
+// Exact size of the body written by `multipart_media_content`
+pub fn multipart_content_length(
+    ranges: &[ByteRange],
+    boundary: &str,
+    content_type: &str,
+    total: u64,
+) -> u64 {
+    let parts: u64 = ranges
+        .iter()
+        .map(|range| {
+            multipart_part_header(boundary, content_type, *range, total).len() as u64
+                + range.length()
+        })
+        .sum();
+
+    parts + multipart_closing(boundary).len() as u64
+}
+
+// `multipart/byteranges` body streaming every range after its part header.
+// Parts are read one after the other, so the cloned handles sharing the
+// file offset never interleave their seeks and reads.
+pub async fn multipart_media_content(
+    file: File,
+    ranges: Vec<ByteRange>,
+    boundary: &str,
+    content_type: &str,
+    total: u64,
+) -> Body {
+    let file = file.into_std().await;
+    let closing = Bytes::from(multipart_closing(boundary));
+    let parts: Vec<_> = ranges
+        .into_iter()
+        .map(|range| {
+            let header = multipart_part_header(boundary, content_type, range, total);
+            (Bytes::from(header), range)
+        })
+        .collect();
+
+    let body = stream::iter(parts)
+        .then(move |(header, range)| {
+            let file = file.try_clone().map(File::from_std);
+
+            async move {
+                let mut file = file?;
+                file.seek(SeekFrom::Start(range.start)).await?;
+
+                let content =
+                    ReaderStream::with_capacity(file.take(range.length()), STREAM_BUFFER_SIZE);
+
+                Ok::<_, std::io::Error>(stream::once(async { Ok(header) }).chain(content))
+            }
+        })
+        .try_flatten()
+        .chain(stream::once(async { Ok(closing) }));
+
+    Body::from_stream(body)
+}
+
+// `1*DIGIT`, `str::parse` alone would also accept a leading `+`
+fn parse_position(value: &str) -> Option<u64> {
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+// One `first-last`, `first-` or `-suffix` spec, `None` when it does not
+// overlap the file
+fn parse_range_spec(spec: &str, file_size: u64) -> Result<Option<ByteRange>, RangeError> {
+    let (first, last) = spec.split_once('-').ok_or(RangeError::Invalid)?;
+    let (first, last) = (first.trim(), last.trim());
+
+    if first.is_empty() {
+        let suffix = parse_position(last).ok_or(RangeError::Invalid)?;
+
+        if suffix == 0 || file_size == 0 {
+            return Ok(None);
+        }
+
+        return Ok(Some(ByteRange {
+            start: file_size.saturating_sub(suffix),
+            end: file_size - 1,
+        }));
+    }
+
+    let start = parse_position(first).ok_or(RangeError::Invalid)?;
+    let end = match last {
+        "" => None,
+        last => Some(parse_position(last).ok_or(RangeError::Invalid)?),
+    };
+
+    if end.is_some_and(|end| end < start) {
+        return Err(RangeError::Invalid);
+    }
+
+    if start >= file_size {
+        return Ok(None);
+    }
+
+    Ok(Some(ByteRange {
+        start,
+        end: end.map_or(file_size - 1, |end| end.min(file_size - 1)),
+    }))
+}
+
+// RFC 7233 `Range: bytes=...` parsing. Ranges ending past the file are
+// clamped to it, suffix ranges count from its end, and whitespace around
+// the unit, the specs and their bounds is tolerated.
+pub fn parse_range_header(header: &str, file_size: u64) -> Result<Vec<ByteRange>, RangeError> {
+    let (unit, specs) = header.split_once('=').ok_or(RangeError::Invalid)?;
+
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return Err(RangeError::Invalid);
+    }
+
+    let mut ranges = Vec::new();
+    let mut has_spec = false;
+
+    // Empty list elements such as `0-1,,5-6` are allowed by the list syntax
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        has_spec = true;
+
+        if let Some(range) = parse_range_spec(spec, file_size)? {
+            ranges.push(range);
+        }
+    }
+
+    if !has_spec {
+        return Err(RangeError::Invalid);
+    }
+
+    if ranges.is_empty() {
+        return Err(RangeError::Unsatisfiable);
+    }
+
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u64 = 10_000;
+
+    fn range(start: u64, end: u64) -> ByteRange {
+        ByteRange { start, end }
+    }
+
+    fn validators() -> FileValidators {
+        FileValidators {
+            etag: String::from("\"2a-2710-5f\""),
+            last_modified: httpdate::parse_http_date("Sun, 18 Oct 2026 10:00:00 GMT").ok(),
+        }
+    }
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn open_ended_range_from_the_start() {
+        // Chrome, Firefox and VLC open a stream with `bytes=0-`
+        assert_eq!(
+            parse_range_header("bytes=0-", SIZE),
+            Ok(vec![range(0, 9_999)])
+        );
+    }
+
+    #[test]
+    fn safari_probe() {
+        // Safari asks for the first two bytes before streaming
+        assert_eq!(parse_range_header("bytes=0-1", SIZE), Ok(vec![range(0, 1)]));
+    }
+
+    #[test]
+    fn resume_from_an_offset() {
+        // VLC seeking or resuming playback
+        assert_eq!(
+            parse_range_header("bytes=5000-", SIZE),
+            Ok(vec![range(5_000, 9_999)])
+        );
+    }
+
+    #[test]
+    fn single_byte_ranges() {
+        assert_eq!(parse_range_header("bytes=0-0", SIZE), Ok(vec![range(0, 0)]));
+        assert_eq!(
+            parse_range_header("bytes=9999-9999", SIZE),
+            Ok(vec![range(9_999, 9_999)])
+        );
+    }
+
+    #[test]
+    fn end_past_the_file_is_clamped() {
+        assert_eq!(
+            parse_range_header("bytes=9000-19999", SIZE),
+            Ok(vec![range(9_000, 9_999)])
+        );
+    }
+
+    #[test]
+    fn suffix_range() {
+        // Safari and VLC read the end of MP4 files for the `moov` atom
+        assert_eq!(
+            parse_range_header("bytes=-500", SIZE),
+            Ok(vec![range(9_500, 9_999)])
+        );
+    }
+
+    #[test]
+    fn suffix_longer_than_the_file_covers_all_of_it() {
+        assert_eq!(
+            parse_range_header("bytes=-20000", SIZE),
+            Ok(vec![range(0, 9_999)])
+        );
+    }
+
+    #[test]
+    fn empty_suffix_is_unsatisfiable() {
+        assert_eq!(
+            parse_range_header("bytes=-0", SIZE),
+            Err(RangeError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn start_past_the_file_is_unsatisfiable() {
+        assert_eq!(
+            parse_range_header("bytes=10000-", SIZE),
+            Err(RangeError::Unsatisfiable)
+        );
+        assert_eq!(
+            parse_range_header("bytes=20000-30000", SIZE),
+            Err(RangeError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn empty_file_is_unsatisfiable() {
+        assert_eq!(
+            parse_range_header("bytes=0-", 0),
+            Err(RangeError::Unsatisfiable)
+        );
+        assert_eq!(
+            parse_range_header("bytes=-500", 0),
+            Err(RangeError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn whitespace_is_tolerated() {
+        assert_eq!(
+            parse_range_header(" bytes = 0 - 499 ", SIZE),
+            Ok(vec![range(0, 499)])
+        );
+        assert_eq!(
+            parse_range_header("bytes=0-1, 5-6", SIZE),
+            Ok(vec![range(0, 1), range(5, 6)])
+        );
+    }
+
+    #[test]
+    fn unit_is_case_insensitive() {
+        assert_eq!(parse_range_header("Bytes=0-1", SIZE), Ok(vec![range(0, 1)]));
+        assert_eq!(parse_range_header("BYTES=0-1", SIZE), Ok(vec![range(0, 1)]));
+    }
+
+    #[test]
+    fn unknown_unit_is_invalid() {
+        assert_eq!(
+            parse_range_header("items=0-1", SIZE),
+            Err(RangeError::Invalid)
+        );
+        assert_eq!(
+            parse_range_header("bytes 0-1", SIZE),
+            Err(RangeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn malformed_specs_are_invalid() {
+        for header in [
+            "bytes=",
+            "bytes=-",
+            "bytes=,",
+            "bytes=abc",
+            "bytes=1-a",
+            "bytes=+1-2",
+            "bytes=1-+2",
+            "bytes=0x10-",
+            "bytes=1.5-2",
+            "bytes=0-1-2",
+        ] {
+            assert_eq!(
+                parse_range_header(header, SIZE),
+                Err(RangeError::Invalid),
+                "{}",
+                header
+            );
+        }
+    }
+
+    #[test]
+    fn reversed_range_is_invalid() {
+        assert_eq!(
+            parse_range_header("bytes=500-100", SIZE),
+            Err(RangeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn one_malformed_spec_invalidates_the_header() {
+        assert_eq!(
+            parse_range_header("bytes=0-1,x-2", SIZE),
+            Err(RangeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn multiple_ranges_keep_their_order() {
+        assert_eq!(
+            parse_range_header("bytes=500-999,0-99,-100", SIZE),
+            Ok(vec![range(500, 999), range(0, 99), range(9_900, 9_999)])
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_ranges_are_dropped_from_a_set() {
+        assert_eq!(
+            parse_range_header("bytes=0-1,20000-", SIZE),
+            Ok(vec![range(0, 1)])
+        );
+    }
+
+    #[test]
+    fn empty_list_elements_are_skipped() {
+        assert_eq!(
+            parse_range_header("bytes=0-1,,5-6,", SIZE),
+            Ok(vec![range(0, 1), range(5, 6)])
+        );
+    }
+
+    #[test]
+    fn content_range_headers() {
+        assert_eq!(get_content_range(0, 499, SIZE), "bytes 0-499/10000");
+        assert_eq!(unsatisfied_content_range(SIZE), "bytes */10000");
+    }
+
+    #[test]
+    fn range_length() {
+        assert_eq!(range(0, 0).length(), 1);
+        assert_eq!(range(100, 199).length(), 100);
+    }
+
+    #[test]
+    fn overlapping_and_adjacent_ranges_are_coalesced() {
+        assert_eq!(
+            coalesce_ranges(vec![range(0, 99), range(50, 149), range(150, 199)]),
+            vec![range(0, 199)]
+        );
+        assert_eq!(
+            coalesce_ranges(vec![range(0, 999), range(100, 199)]),
+            vec![range(0, 999)]
+        );
+    }
+
+    #[test]
+    fn disjoint_ranges_are_sorted() {
+        assert_eq!(
+            coalesce_ranges(vec![range(500, 599), range(0, 99), range(9_900, 9_999)]),
+            vec![range(0, 99), range(500, 599), range(9_900, 9_999)]
+        );
+    }
+
+    #[test]
+    fn boundaries_are_unique() {
+        assert_ne!(multipart_boundary(), multipart_boundary());
+    }
+
+    #[tokio::test]
+    async fn multipart_body() {
+        let path = std::env::temp_dir().join(format!("stream-service-{}", multipart_boundary()));
+        let content: Vec<u8> = (0..=255).collect();
+        std::fs::write(&path, &content).unwrap();
+
+        let ranges = vec![range(0, 3), range(250, 255)];
+        let file = File::open(&path).await.unwrap();
+        let body = multipart_media_content(file, ranges.clone(), "sep", "video/mp4", 256).await;
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut expected =
+            b"\r\n--sep\r\nContent-Type: video/mp4\r\nContent-Range: bytes 0-3/256\r\n\r\n"
+                .to_vec();
+        expected.extend_from_slice(&content[0..=3]);
+        expected.extend_from_slice(
+            b"\r\n--sep\r\nContent-Type: video/mp4\r\nContent-Range: bytes 250-255/256\r\n\r\n",
+        );
+        expected.extend_from_slice(&content[250..=255]);
+        expected.extend_from_slice(b"\r\n--sep--\r\n");
+
+        assert_eq!(body.as_ref(), expected.as_slice());
+        assert_eq!(
+            multipart_content_length(&ranges, "sep", "video/mp4", 256),
+            expected.len() as u64
+        );
+    }
+
+    #[test]
+    fn validators_of_a_file() {
+        let path = std::env::temp_dir().join(format!("stream-service-{}", multipart_boundary()));
+        std::fs::write(&path, b"content").unwrap();
+        let validators = file_validators(&std::fs::metadata(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        // Strong tag holding the size, and a date matching its header
+        let (opaque, weak) = parse_entity_tag(&validators.etag).unwrap();
+        assert!(!weak);
+        assert_eq!(opaque.split('-').nth(1), Some("7"));
+
+        let header = validators.last_modified_header().unwrap();
+        assert_eq!(
+            httpdate::parse_http_date(&header).ok(),
+            validators.last_modified
+        );
+    }
+
+    #[test]
+    fn if_none_match() {
+        let validators = validators();
+
+        for value in [
+            "\"2a-2710-5f\"",
+            "W/\"2a-2710-5f\"",
+            "\"other\", \"2a-2710-5f\"",
+            "*",
+        ] {
+            assert!(
+                is_not_modified(&headers(header::IF_NONE_MATCH, value), &validators),
+                "{}",
+                value
+            );
+        }
+
+        assert!(!is_not_modified(
+            &headers(header::IF_NONE_MATCH, "\"other\""),
+            &validators
+        ));
+        assert!(!is_not_modified(&HeaderMap::new(), &validators));
+    }
+
+    #[test]
+    fn if_none_match_takes_precedence_over_if_modified_since() {
+        let mut headers = headers(header::IF_NONE_MATCH, "\"other\"");
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            "Sun, 18 Oct 2026 10:00:00 GMT".parse().unwrap(),
+        );
+
+        assert!(!is_not_modified(&headers, &validators()));
+    }
+
+    #[test]
+    fn if_modified_since() {
+        let validators = validators();
+
+        for (value, not_modified) in [
+            ("Sun, 18 Oct 2026 10:00:00 GMT", true),
+            ("Sun, 18 Oct 2026 11:00:00 GMT", true),
+            ("Sun, 18 Oct 2026 09:59:59 GMT", false),
+            ("yesterday", false),
+        ] {
+            assert_eq!(
+                is_not_modified(&headers(header::IF_MODIFIED_SINCE, value), &validators),
+                not_modified,
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn if_range() {
+        let validators = validators();
+
+        assert!(if_range_matches(&HeaderMap::new(), &validators));
+
+        for (value, matches) in [
+            ("\"2a-2710-5f\"", true),
+            ("\"other\"", false),
+            // Weak tags never match an `If-Range`
+            ("W/\"2a-2710-5f\"", false),
+            ("Sun, 18 Oct 2026 10:00:00 GMT", true),
+            ("Sun, 18 Oct 2026 11:00:00 GMT", false),
+            ("garbage", false),
+        ] {
+            assert_eq!(
+                if_range_matches(&headers(header::IF_RANGE, value), &validators),
+                matches,
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn content_type_from_the_extension() {
+        for (path, content_type) in [
+            ("/media/movie.mkv", "video/x-matroska"),
+            ("/media/movie.webm", "video/webm"),
+            ("/media/movie.mp4", "video/mp4"),
+            ("/media/Movie.MKV", "video/x-matroska"),
+            ("/media/recording.ts", "video/mp2t"),
+            ("/media/bluray.m2ts", "video/mp2t"),
+        ] {
+            assert_eq!(
+                media_content_type(path, Some("mov,mp4,m4a,3gp,3g2,mj2")),
+                content_type,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn content_type_from_the_container() {
+        assert_eq!(
+            media_content_type("/media/movie", Some("matroska,webm")),
+            "video/x-matroska"
+        );
+        assert_eq!(
+            media_content_type("/media/recording.bin", Some("mpegts")),
+            "video/mp2t"
+        );
+        assert_eq!(
+            media_content_type("/media/movie", Some("unknown")),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            media_content_type("/media/movie", None),
+            "application/octet-stream"
+        );
+    }
+}
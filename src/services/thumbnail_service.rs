@@ -0,0 +1,392 @@
+use crate::entities::artwork::{self, ArtworkKind};
+use crate::entities::{media, media_stream};
+use crate::models::FrameFormat;
+use crate::services::{
+    cache_dir, container_seconds, open_video_decoder, write_cache, ArtworkImage,
+};
+use ffmpeg_next::format::context::Input;
+use ffmpeg_next::format::Pixel;
+use ffmpeg_next::software::scaling;
+use ffmpeg_next::{decoder, ffi, frame, Discard, Packet};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait,
+    PaginatorTrait, QueryFilter, Set,
+};
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+// Width of generated thumbnails, narrower videos keep their own width
+const THUMBNAIL_WIDTH: u32 = 640;
+
+// Average luma (0-255) under which a frame is considered black
+const MIN_BRIGHTNESS: f64 = 24.0;
+
+// Luma standard deviation under which a frame is considered uniform, such as
+// fades or plain title cards
+const MIN_DETAIL: f64 = 12.0;
+
+// Decodes single frames of the best video stream of a file
+pub struct VideoFrames {
+    ictx: Input,
+    stream_index: usize,
+    decoder: decoder::Video,
+    // Seconds per stream timestamp unit
+    time_base: f64,
+    // Container start time in seconds, timestamps do not always start at zero
+    start_time: f64,
+}
+
+impl VideoFrames {
+    pub fn open(path: &Path) -> Result<Self, ffmpeg_next::Error> {
+        ffmpeg_next::init()?;
+
+        let ictx = ffmpeg_next::format::input(&path)?;
+        let stream = ictx
+            .streams()
+            .best(ffmpeg_next::media::Type::Video)
+            .ok_or(ffmpeg_next::Error::StreamNotFound)?;
+        let stream_index = stream.index();
+        let time_base = f64::from(stream.time_base());
+        let decoder = open_video_decoder(&stream)?;
+
+        let start_time = unsafe { (*ictx.as_ptr()).start_time };
+        let start_time = if start_time == ffi::AV_NOPTS_VALUE {
+            0.0
+        } else {
+            container_seconds(start_time)
+        };
+
+        Ok(VideoFrames {
+            ictx,
+            stream_index,
+            decoder,
+            time_base,
+            start_time,
+        })
+    }
+
+    pub fn duration(&self) -> Option<f64> {
+        (self.ictx.duration() > 0).then(|| container_seconds(self.ictx.duration()))
+    }
+
+    // Seconds since the start of the file at which a decoded frame is shown
+    fn shown_at(&self, frame: &frame::Video) -> Option<f64> {
+        frame
+            .timestamp()
+            .map(|timestamp| timestamp as f64 * self.time_base - self.start_time)
+    }
+
+    // Feeds the packets of the video stream, then the end of the stream, to
+    // the decoder and hands every decoded frame over until `on_frame` returns
+    // false
+    fn decode<F>(&mut self, mut on_frame: F) -> Result<(), ffmpeg_next::Error>
+    where
+        F: FnMut(&Self, &mut frame::Video) -> Result<bool, ffmpeg_next::Error>,
+    {
+        let mut decoded = frame::Video::empty();
+
+        loop {
+            let mut packet = Packet::empty();
+            let end_of_stream = match packet.read(&mut self.ictx) {
+                Ok(()) if packet.stream() != self.stream_index => continue,
+                Ok(()) => false,
+                Err(ffmpeg_next::Error::Eof) => true,
+                // A damaged packet only costs the frames that depend on it
+                Err(_) => continue,
+            };
+
+            if end_of_stream {
+                self.decoder.send_eof()?;
+            } else if self.decoder.send_packet(&packet).is_err() {
+                continue;
+            }
+
+            while self.decoder.receive_frame(&mut decoded).is_ok() {
+                if !on_frame(self, &mut decoded)? {
+                    return Ok(());
+                }
+            }
+
+            if end_of_stream {
+                return Ok(());
+            }
+        }
+    }
+
+    // The frame on screen at `seconds`: the last one shown at or before it,
+    // decoded forward from the keyframe before it. The first frame when
+    // `seconds` precedes it, the last one when `seconds` is past the end.
+    pub fn frame_at(&mut self, seconds: f64) -> Result<Option<frame::Video>, ffmpeg_next::Error> {
+        let seconds = seconds.max(0.0);
+        let timestamp = ((self.start_time + seconds) * f64::from(ffi::AV_TIME_BASE)) as i64;
+
+        self.ictx.seek(timestamp, ..timestamp)?;
+        self.decoder.flush();
+
+        let mut found = None;
+
+        // Frames are moved out of the decoder's one rather than copied, the
+        // keyframe can be hundreds of frames before `seconds`
+        self.decode(|frames, decoded| match frames.shown_at(decoded) {
+            Some(shown_at) if shown_at < seconds => {
+                found = Some(std::mem::replace(decoded, frame::Video::empty()));
+                Ok(true)
+            }
+            Some(shown_at) if shown_at > seconds && found.is_some() => Ok(false),
+            _ => {
+                found = Some(std::mem::replace(decoded, frame::Video::empty()));
+                Ok(false)
+            }
+        })?;
+
+        Ok(found)
+    }
+
+    // Reads the stream from its start and hands over the first keyframe shown
+    // in every `interval` seconds slot, along with the start of the slot. The
+    // other frames are dropped by the decoder without being decoded, a slot
+    // without a keyframe gets no frame.
+    pub fn sample_every<F>(
+        &mut self,
+        interval: f64,
+        mut on_frame: F,
+    ) -> Result<(), ffmpeg_next::Error>
+    where
+        F: FnMut(f64, &frame::Video) -> Result<(), ffmpeg_next::Error>,
+    {
+        let mut next_slot = 0.0;
+
+        self.decoder.skip_frame(Discard::NonKey);
+
+        let decoded = self.decode(|frames, decoded| {
+            let Some(shown_at) = frames.shown_at(decoded) else {
+                return Ok(true);
+            };
+
+            if shown_at >= next_slot {
+                let slot = (shown_at / interval).floor() * interval;
+                on_frame(slot, decoded)?;
+                next_slot = slot + interval;
+            }
+
+            Ok(true)
+        });
+
+        self.decoder.skip_frame(Discard::Default);
+
+        decoded
+    }
+}
+
+// Scales a decoded frame to `width` (never enlarging it) and converts it to
+// RGB, anamorphic videos are stretched to their display aspect ratio
+pub fn frame_to_image(frame: &frame::Video, width: u32) -> Result<RgbImage, ffmpeg_next::Error> {
+    let sample_aspect = frame.aspect_ratio();
+    let display_width = if sample_aspect.numerator() > 0 && sample_aspect.denominator() > 0 {
+        (f64::from(frame.width()) * f64::from(sample_aspect)).round() as u32
+    } else {
+        frame.width()
+    };
+
+    let width = width.min(display_width).max(2) & !1;
+    let height = u64::from(frame.height()) * u64::from(width) / u64::from(display_width.max(1));
+    let height = (height as u32).max(2) & !1;
+
+    let mut scaler = scaling::Context::get(
+        frame.format(),
+        frame.width(),
+        frame.height(),
+        Pixel::RGB24,
+        width,
+        height,
+        scaling::Flags::BILINEAR,
+    )?;
+
+    let mut rgb = frame::Video::empty();
+    scaler.run(frame, &mut rgb)?;
+
+    let stride = rgb.stride(0);
+    let row_length = width as usize * 3;
+    let pixels = rgb
+        .data(0)
+        .chunks(stride)
+        .take(height as usize)
+        .flat_map(|row| &row[..row_length])
+        .copied()
+        .collect();
+
+    RgbImage::from_raw(width, height, pixels).ok_or(ffmpeg_next::Error::InvalidData)
+}
+
+// Mean and standard deviation of the luma of an image
+fn luma_stats(image: &RgbImage) -> (f64, f64) {
+    let lumas: Vec<f64> = image
+        .pixels()
+        .map(|pixel| {
+            0.299 * f64::from(pixel[0]) + 0.587 * f64::from(pixel[1]) + 0.114 * f64::from(pixel[2])
+        })
+        .collect();
+
+    let count = lumas.len().max(1) as f64;
+    let mean = lumas.iter().sum::<f64>() / count;
+    let variance = lumas.iter().map(|luma| (luma - mean).powi(2)).sum::<f64>() / count;
+
+    (mean, variance.sqrt())
+}
+
+// Spread over the first half of the video, past the black leaders and intros
+fn candidate_positions(duration: Option<f64>) -> Vec<f64> {
+    match duration {
+        Some(duration) => (1..=10)
+            .map(|step| duration * f64::from(step) / 20.0)
+            .collect(),
+        None => vec![0.0, 5.0, 10.0, 30.0, 60.0],
+    }
+}
+
+// A representative frame of the video: the first candidate that is neither
+// black nor uniform, or the most detailed one when none qualifies
+pub fn extract_thumbnail(path: &Path) -> Result<RgbImage, ffmpeg_next::Error> {
+    let mut frames = VideoFrames::open(path)?;
+    let mut best: Option<(f64, RgbImage)> = None;
+
+    for seconds in candidate_positions(frames.duration()) {
+        let Some(frame) = frames.frame_at(seconds)? else {
+            continue;
+        };
+
+        let image = frame_to_image(&frame, THUMBNAIL_WIDTH)?;
+        let (brightness, detail) = luma_stats(&image);
+
+        if brightness >= MIN_BRIGHTNESS && detail >= MIN_DETAIL {
+            return Ok(image);
+        }
+
+        if best
+            .as_ref()
+            .is_none_or(|(best_detail, _)| detail > *best_detail)
+        {
+            best = Some((detail, image));
+        }
+    }
+
+    best.map(|(_, image)| image)
+        .ok_or(ffmpeg_next::Error::StreamNotFound)
+}
+
+// The exact frame shown at `seconds`, scaled down to `width` and encoded
+pub fn extract_frame(
+    path: &Path,
+    seconds: f64,
+    width: Option<u32>,
+    format: FrameFormat,
+) -> io::Result<ArtworkImage> {
+    let mut frames = VideoFrames::open(path).map_err(io::Error::other)?;
+    let frame = frames
+        .frame_at(seconds)
+        .map_err(io::Error::other)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no frame could be decoded"))?;
+
+    let image = frame_to_image(&frame, width.filter(|width| *width > 0).unwrap_or(u32::MAX))
+        .map_err(io::Error::other)?;
+
+    let format = match format {
+        FrameFormat::Jpeg => ImageFormat::Jpeg,
+        FrameFormat::Png => ImageFormat::Png,
+        FrameFormat::Webp => ImageFormat::WebP,
+    };
+
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut bytes), format)
+        .map_err(io::Error::other)?;
+
+    Ok(ArtworkImage {
+        bytes,
+        mime_type: format.to_mime_type(),
+    })
+}
+
+fn generate_thumbnail(path: &Path, target: &Path) -> io::Result<()> {
+    let image = extract_thumbnail(path).map_err(io::Error::other)?;
+
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+        .map_err(io::Error::other)?;
+
+    write_cache(target, &bytes)
+}
+
+// Home videos and recordings never come with artwork, a frame of the video
+// stands in for it. Extracted again when the file changed or the cached
+// image was removed, dropped once real artwork shows up.
+pub async fn ensure_thumbnail(
+    db: &DatabaseConnection,
+    media: &media::Model,
+    refresh: bool,
+) -> Result<(), DbErr> {
+    let artwork = artwork::Entity::find()
+        .filter(artwork::Column::MediaId.eq(media.id))
+        .all(db)
+        .await?;
+
+    let generated = artwork.iter().find(|artwork| artwork.is_generated);
+    let has_artwork = artwork.iter().any(|artwork| {
+        !artwork.is_generated && matches!(artwork.kind, ArtworkKind::Poster | ArtworkKind::Thumb)
+    });
+
+    if has_artwork {
+        if let Some(generated) = generated {
+            let _ = std::fs::remove_file(&generated.path);
+            generated.clone().delete(db).await?;
+        }
+
+        return Ok(());
+    }
+
+    if generated.is_some_and(|generated| !refresh && Path::new(&generated.path).is_file()) {
+        return Ok(());
+    }
+
+    let video_streams = media_stream::Entity::find()
+        .filter(media_stream::Column::MediaId.eq(media.id))
+        .filter(media_stream::Column::Medium.eq("Video"))
+        .count(db)
+        .await?;
+
+    if video_streams == 0 {
+        return Ok(());
+    }
+
+    let thumbnail = cache_dir()
+        .join("thumbnails")
+        .join(format!("{}.jpg", media.id));
+
+    let source = PathBuf::from(&media.path);
+    let target = thumbnail.clone();
+    let result = tokio::task::spawn_blocking(move || generate_thumbnail(&source, &target))
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    if let Err(e) = result {
+        eprintln!("could not extract a thumbnail from {}: {}", media.path, e);
+        return Ok(());
+    }
+
+    if generated.is_none() {
+        artwork::ActiveModel {
+            media_id: Set(media.id),
+            kind: Set(ArtworkKind::Thumb),
+            path: Set(thumbnail.to_string_lossy().to_string()),
+            stream_index: Set(None),
+            is_generated: Set(true),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(())
+}
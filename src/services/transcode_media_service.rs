@@ -34,6 +34,13 @@ pub struct SubtitleTranscoder {
     last_log_frame: Instant,
 }
 
+// Decoder for a video input stream, shared by transcoding and frame extraction
+pub fn open_video_decoder(ist: &format::stream::Stream) -> Result<decoder::Video, ffmpeg::Error> {
+    Context::from_parameters(ist.parameters())?
+        .decoder()
+        .video()
+}
+
 pub trait Transcoder {
     fn new(
         ist: &format::stream::Stream,
@@ -147,9 +154,7 @@ impl Transcoder for VideoTranscoder {
         // On vérifie s'il y a des headers Globaux (commun sur le x264).
         let global_header = octx.format().flags().contains(format::Flags::GLOBAL_HEADER);
         // Chercher le decoder
-        let decoder = Context::from_parameters(ist.parameters())?
-            .decoder()
-            .video()?;
+        let decoder = open_video_decoder(ist)?;
 
         // Chercher le codec h264
         let codec = encoder::find(codec::Id::H264);
@@ -0,0 +1,240 @@
+use crate::entities::{media, media_stream};
+use crate::services::{cache_dir, frame_to_image, write_cache, VideoFrames};
+use crate::state::AppState;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use sea_orm::sea_query::Query;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use std::collections::HashSet;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Seconds between two preview images
+pub const TRICKPLAY_INTERVAL: f64 = 10.0;
+
+const TILE_WIDTH: u32 = 320;
+const SHEET_COLUMNS: u32 = 10;
+const SHEET_ROWS: u32 = 10;
+
+// Files added by the library watcher are picked up on this period
+const RECHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// Video streams of these codecs are cover images, not videos
+const IMAGE_CODECS: [&str; 5] = ["mjpeg", "png", "bmp", "gif", "webp"];
+
+pub fn trickplay_dir(media_id: i32) -> PathBuf {
+    cache_dir().join("trickplay").join(media_id.to_string())
+}
+
+pub fn trickplay_vtt(media_id: i32) -> PathBuf {
+    trickplay_dir(media_id).join("trickplay.vtt")
+}
+
+pub fn trickplay_sheet(media_id: i32, sheet: u32) -> PathBuf {
+    trickplay_dir(media_id).join(format!("{}.jpg", sheet))
+}
+
+// Previews of a file that changed no longer match it
+pub fn clear_trickplay(media_id: i32) {
+    let dir = trickplay_dir(media_id);
+
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        if e.kind() != io::ErrorKind::NotFound {
+            eprintln!("could not remove {}: {}", dir.display(), e);
+        }
+    }
+}
+
+// "01:02:03.500"
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn write_sheet(media_id: i32, sheet: u32, tiles: &[RgbImage]) -> io::Result<()> {
+    let Some(first) = tiles.first() else {
+        return Ok(());
+    };
+
+    let (tile_width, tile_height) = first.dimensions();
+    let columns = SHEET_COLUMNS.min(tiles.len() as u32);
+    let rows = (tiles.len() as u32).div_ceil(SHEET_COLUMNS);
+    let mut image = RgbImage::new(columns * tile_width, rows * tile_height);
+
+    for (position, tile) in tiles.iter().enumerate() {
+        let position = position as u32;
+        imageops::replace(
+            &mut image,
+            tile,
+            i64::from(position % SHEET_COLUMNS * tile_width),
+            i64::from(position / SHEET_COLUMNS * tile_height),
+        );
+    }
+
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+        .map_err(io::Error::other)?;
+
+    write_cache(&trickplay_sheet(media_id, sheet), &bytes)
+}
+
+// Decodes a keyframe every `TRICKPLAY_INTERVAL` seconds, tiles them into sprite
+// sheets and describes them in a WebVTT track whose cues point at the tiles
+// with `#xywh=` fragments. The track is written last, it marks completion.
+pub fn generate_trickplay(path: &Path, media_id: i32) -> io::Result<()> {
+    let mut frames = VideoFrames::open(path).map_err(io::Error::other)?;
+    let duration = frames.duration();
+
+    let mut tiles: Vec<RgbImage> = Vec::new();
+    let mut cues: Vec<(f64, u32, u32, u32)> = Vec::new();
+    let mut tile_size = None;
+    let mut sheet = 0;
+
+    clear_trickplay(media_id);
+
+    let mut sheet_error = None;
+
+    let decoded = frames.sample_every(TRICKPLAY_INTERVAL, |start, frame| {
+        let mut tile = frame_to_image(frame, TILE_WIDTH)?;
+        let (width, height) = *tile_size.get_or_insert(tile.dimensions());
+
+        // Streams that change resolution midway keep the first tile size
+        if tile.dimensions() != (width, height) {
+            tile = imageops::resize(&tile, width, height, FilterType::Triangle);
+        }
+
+        let position = tiles.len() as u32;
+        cues.push((
+            start,
+            sheet,
+            position % SHEET_COLUMNS * width,
+            position / SHEET_COLUMNS * height,
+        ));
+        tiles.push(tile);
+
+        if tiles.len() as u32 == SHEET_COLUMNS * SHEET_ROWS {
+            if let Err(e) = write_sheet(media_id, sheet, &tiles) {
+                sheet_error = Some(e);
+                return Err(ffmpeg_next::Error::Exit);
+            }
+
+            tiles.clear();
+            sheet += 1;
+        }
+
+        Ok(())
+    });
+
+    if let Some(e) = sheet_error {
+        return Err(e);
+    }
+
+    decoded.map_err(io::Error::other)?;
+
+    write_sheet(media_id, sheet, &tiles)?;
+
+    let Some((width, height)) = tile_size else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no frame could be decoded",
+        ));
+    };
+
+    let mut vtt = String::from("WEBVTT\n");
+
+    for (index, (start, sheet, x, y)) in cues.iter().enumerate() {
+        let end = cues
+            .get(index + 1)
+            .map(|(next_start, ..)| *next_start)
+            .unwrap_or_else(|| duration.unwrap_or(0.0).max(start + TRICKPLAY_INTERVAL));
+
+        vtt.push_str(&format!(
+            "\n{} --> {}\n/medias/{}/trickplay/{}.jpg#xywh={},{},{},{}\n",
+            vtt_timestamp(*start),
+            vtt_timestamp(end),
+            media_id,
+            sheet,
+            x,
+            y,
+            width,
+            height
+        ));
+    }
+
+    write_cache(&trickplay_vtt(media_id), vtt.as_bytes())
+}
+
+// Present video files that have no previews yet
+async fn pending_trickplay(
+    db: &DatabaseConnection,
+    failed: &HashSet<i32>,
+) -> Result<Vec<media::Model>, DbErr> {
+    let media = media::Entity::find()
+        .filter(media::Column::Missing.eq(false))
+        .filter(media::Column::Duration.gt(0.0))
+        .filter(
+            media::Column::Id.in_subquery(
+                Query::select()
+                    .column(media_stream::Column::MediaId)
+                    .from(media_stream::Entity)
+                    .and_where(media_stream::Column::Medium.eq("Video"))
+                    .and_where(media_stream::Column::Codec.is_not_in(IMAGE_CODECS))
+                    .to_owned(),
+            ),
+        )
+        .order_by_asc(media::Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(media
+        .into_iter()
+        .filter(|media| !failed.contains(&media.id) && !trickplay_vtt(media.id).is_file())
+        .collect())
+}
+
+// Generates the missing previews one file at a time. Woken up after scans and
+// when a client asks for previews that do not exist yet.
+pub async fn generate_trickplay_previews(state: AppState) {
+    // Files that cannot be decoded are not retried until the next restart
+    let mut failed = HashSet::new();
+
+    loop {
+        match pending_trickplay(&state.db, &failed).await {
+            Ok(pending) => {
+                for media in pending {
+                    let path = PathBuf::from(&media.path);
+                    let result =
+                        tokio::task::spawn_blocking(move || generate_trickplay(&path, media.id))
+                            .await;
+
+                    match result {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => {
+                            eprintln!("could not generate previews for {}: {}", media.path, e);
+                            failed.insert(media.id);
+                        }
+                        Err(e) => {
+                            eprintln!("preview generation for {} failed: {}", media.path, e);
+                            failed.insert(media.id);
+                        }
+                    }
+                }
+            }
+            Err(e) => eprintln!("could not list media without previews: {}", e),
+        }
+
+        tokio::select! {
+            _ = state.trickplay_requested.notified() => {}
+            _ = tokio::time::sleep(RECHECK_INTERVAL) => {}
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use crate::entities::library;
+use crate::services::{
+    collect_library_files, index_file, is_library_file, library_for_path, library_roots,
+    list_libraries, mark_missing, prune_items, prune_tv_catalog, rename_media,
+};
+use crate::state::AppState;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sea_orm::{DatabaseConnection, DbErr};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+// How long a file must stay quiet before it is indexed, so that copies and
+// downloads still in progress are not probed half-written.
+const DEBOUNCE: Duration = Duration::from_secs(5);
+
+enum PendingChange {
+    Upsert { size: Option<u64> },
+    Remove,
+}
+
+struct Pending {
+    change: PendingChange,
+    last_event: Instant,
+}
+
+// Libraries with watching enabled and the roots currently registered with the watcher
+struct WatchedLibraries {
+    libraries: Vec<library::Model>,
+    roots: Vec<PathBuf>,
+}
+
+async fn watch_libraries(
+    db: &DatabaseConnection,
+    watcher: &mut RecommendedWatcher,
+    watched: &mut WatchedLibraries,
+) -> Result<(), DbErr> {
+    for root in watched.roots.drain(..) {
+        let _ = watcher.unwatch(&root);
+    }
+
+    watched.libraries = list_libraries(db)
+        .await?
+        .into_iter()
+        .filter(|library| library.scan_options.watch)
+        .collect();
+
+    for root in watched.libraries.iter().flat_map(library_roots) {
+        match watcher.watch(&root, RecursiveMode::Recursive) {
+            Ok(()) => watched.roots.push(root),
+            Err(e) => eprintln!("could not watch {}: {}", root.display(), e),
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn watch_library(state: AppState) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let mut watcher = match notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let _ = tx.send(res);
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("could not start the library watcher: {}", e);
+            return;
+        }
+    };
+
+    let mut watched = WatchedLibraries {
+        libraries: Vec::new(),
+        roots: Vec::new(),
+    };
+
+    if let Err(e) = watch_libraries(&state.db, &mut watcher, &mut watched).await {
+        eprintln!("could not load libraries to watch: {}", e);
+    }
+
+    let mut pending: HashMap<PathBuf, Pending> = HashMap::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(Ok(event)) => {
+                    if let Err(e) = handle_event(&state, &watched, event, &mut pending).await {
+                        eprintln!("library watcher error: {}", e);
+                    }
+                }
+                Some(Err(e)) => eprintln!("library watcher error: {}", e),
+                None => break,
+            },
+            _ = state.libraries_changed.notified() => {
+                if let Err(e) = watch_libraries(&state.db, &mut watcher, &mut watched).await {
+                    eprintln!("could not reload libraries to watch: {}", e);
+                }
+            }
+            _ = ticker.tick() => flush_pending(&state, &watched, &mut pending).await,
+        }
+    }
+}
+
+fn queue_upsert(library: &library::Model, path: &Path, pending: &mut HashMap<PathBuf, Pending>) {
+    // A folder moved into the library arrives as a single event
+    if path.is_dir() {
+        let mut files = Vec::new();
+        if let Err(e) = collect_library_files(library, path, &mut files) {
+            eprintln!("could not scan {}: {}", path.display(), e);
+        }
+
+        for file in files {
+            queue_upsert(library, &file, pending);
+        }
+
+        return;
+    }
+
+    if !is_library_file(library, path) {
+        return;
+    }
+
+    let size = fs::metadata(path).ok().map(|metadata| metadata.len());
+
+    pending.insert(
+        path.to_path_buf(),
+        Pending {
+            change: PendingChange::Upsert { size },
+            last_event: Instant::now(),
+        },
+    );
+}
+
+fn queue_remove(path: &Path, pending: &mut HashMap<PathBuf, Pending>) {
+    pending.retain(|pending_path, _| !pending_path.starts_with(path));
+
+    pending.insert(
+        path.to_path_buf(),
+        Pending {
+            change: PendingChange::Remove,
+            last_event: Instant::now(),
+        },
+    );
+}
+
+async fn handle_event(
+    state: &AppState,
+    watched: &WatchedLibraries,
+    event: Event,
+    pending: &mut HashMap<PathBuf, Pending>,
+) -> Result<(), DbErr> {
+    match event.kind {
+        // Both sides of a move are known, keep the existing rows and their ids
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let (from, to) = (&event.paths[0], &event.paths[1]);
+
+            pending.retain(|pending_path, _| !pending_path.starts_with(from));
+
+            let _guard = state.scan_lock.lock().await;
+
+            match library_for_path(&watched.libraries, to) {
+                Some(library) => {
+                    rename_media(&state.db, from, to).await?;
+                    queue_upsert(library, to, pending);
+                }
+                // Moved out of every library, no scan would ever cover the
+                // new path
+                None => {
+                    mark_missing(&state.db, from).await?;
+                }
+            }
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => {
+            for path in &event.paths {
+                queue_remove(path, pending);
+            }
+        }
+        EventKind::Create(_) | EventKind::Modify(_) => {
+            for path in &event.paths {
+                if let Some(library) = library_for_path(&watched.libraries, path) {
+                    queue_upsert(library, path, pending);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+async fn flush_pending(
+    state: &AppState,
+    watched: &WatchedLibraries,
+    pending: &mut HashMap<PathBuf, Pending>,
+) {
+    let ready: Vec<PathBuf> = pending
+        .iter()
+        .filter(|(_, change)| change.last_event.elapsed() >= DEBOUNCE)
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    if ready.is_empty() {
+        return;
+    }
+
+    // A running scan indexes the same files, the changes wait for a later tick
+    let Ok(_guard) = state.scan_lock.try_lock() else {
+        return;
+    };
+
+    let db = &state.db;
+    let mut changed = false;
+
+    for path in ready {
+        let Some(change) = pending.remove(&path) else {
+            continue;
+        };
+
+        let result = match change.change {
+            PendingChange::Remove => mark_missing(db, &path).await.map(|_| ()),
+            PendingChange::Upsert { size } => {
+                let current_size = fs::metadata(&path).ok().map(|metadata| metadata.len());
+
+                // The file vanished again, its removal event is on its way
+                if current_size.is_none() {
+                    continue;
+                }
+
+                // Still growing, wait for another quiet period
+                if current_size != size {
+                    pending.insert(
+                        path,
+                        Pending {
+                            change: PendingChange::Upsert { size: current_size },
+                            last_event: Instant::now(),
+                        },
+                    );
+                    continue;
+                }
+
+                // The library may have been removed while the change was pending
+                let Some(library) = library_for_path(&watched.libraries, &path) else {
+                    continue;
+                };
+
+                index_file(db, library, &path).await.map(|_| ())
+            }
+        };
+
+        match result {
+            Ok(()) => changed = true,
+            Err(e) => eprintln!("could not update {}: {}", path.display(), e),
+        }
+    }
+
+    // Removed, renamed or regrouped files may have left empty items, seasons
+    // and series behind
+    if changed {
+        let pruned = async {
+            prune_tv_catalog(db).await?;
+            prune_items(db).await
+        }
+        .await;
+
+        if let Err(e) = pruned {
+            eprintln!("could not prune the catalog: {}", e);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub db: DatabaseConnection,
+    // Held for the duration of a library scan or of a watcher update, so two
+    // of them never index the same file at once
+    pub scan_lock: Arc<Mutex<()>>,
+    // Signalled when libraries are added or removed so the watcher follows them
+    pub libraries_changed: Arc<Notify>,
+    // Wakes up the trickplay worker when new files may need previews
+    pub trickplay_requested: Arc<Notify>,
+}
+
+impl AppState {
+    pub fn new(db: DatabaseConnection) -> Self {
+        AppState {
+            db,
+            scan_lock: Arc::new(Mutex::new(())),
+            libraries_changed: Arc::new(Notify::new()),
+            trickplay_requested: Arc::new(Notify::new()),
+        }
+    }
+}